@@ -0,0 +1,589 @@
+use crate::{Components, Description, EntityGraph, EntityGraphError, Error, TraversalOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    fmt::Display,
+    hash::Hash,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// An edge as seen by the cost function of `dijkstra` and `astar`.
+pub struct WeightedEdge<'a, ID, K, R> {
+    pub relationship: &'a R,
+    pub from: &'a ID,
+    pub to: &'a ID,
+    pub from_components: &'a Components<K>,
+    pub to_components: &'a Components<K>,
+    /// The edge's payload, if it was added with one.
+    pub data: Option<&'a Value>,
+}
+
+/// A search frontier entry, ordered so that `BinaryHeap` pops the lowest priority first.
+struct Candidate {
+    priority: f64,
+    cost: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Every entity ordered so that each edge points from an earlier entity to a later one.
+    /// Only edges of `relationship` are considered, or edges of every relationship when it is
+    /// `None`. Fails with `CycleDetected` holding one of the cycles if there is no such order.
+    pub fn topological_sort(
+        &self,
+        relationship: Option<&R>,
+    ) -> Result<Vec<ID>, EntityGraphError<ID>> {
+        let mut cycle = None;
+        let mut order = self.depth_first_order(relationship, |found| {
+            cycle = Some(found);
+            false
+        });
+        if let Some(cycle) = cycle {
+            return Err(EntityGraphError::CycleDetected(cycle));
+        }
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Whether edges of `relationship`, or of every relationship when it is `None`, form a cycle.
+    pub fn has_cycle(&self, relationship: Option<&R>) -> bool {
+        let mut found = false;
+        self.depth_first_order(relationship, |_| {
+            found = true;
+            false
+        });
+        found
+    }
+
+    /// Cycles formed by edges of `relationship`, or of every relationship when it is `None`,
+    /// each starting and ending with the same entity. One cycle is reported per edge that closes
+    /// a cycle during a depth-first search: the result is empty exactly when there are no cycles,
+    /// but cycles sharing those edges are not all listed.
+    pub fn find_cycles(&self, relationship: Option<&R>) -> Vec<Vec<ID>> {
+        let mut cycles = Vec::new();
+        self.depth_first_order(relationship, |cycle| {
+            cycles.push(cycle);
+            true
+        });
+        cycles
+    }
+
+    /// A path with the fewest edges from `from` to `to`, including both ends, following edges
+    /// of `relationship`, or of every relationship when it is `None`. `None` if `to` can't be
+    /// reached.
+    pub fn shortest_path(&self, from: &ID, to: &ID, relationship: Option<&R>) -> Option<Vec<ID>> {
+        let (from, _) = self.entities.get_key_value(from)?;
+        let relationships = relationship.map(std::slice::from_ref);
+        let mut predecessors: HashMap<&ID, Option<&ID>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![id.clone()];
+                let mut current = id;
+                while let Some(Some(previous)) = predecessors.get(current) {
+                    path.push((*previous).clone());
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.filtered_neighbors(id, relationships) {
+                if !predecessors.contains_key(neighbor) {
+                    predecessors.insert(neighbor, Some(id));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+
+    /// The cheapest path from `from` to `to` over edges of every relationship, including both
+    /// ends, and its total cost. `cost` gives the cost of each edge and must not be negative;
+    /// edges with an infinite or NaN cost are never taken. `None` if `to` can't be reached.
+    pub fn dijkstra<F>(&self, from: &ID, to: &ID, cost: F) -> Option<(Vec<ID>, f64)>
+    where
+        F: FnMut(WeightedEdge<'_, ID, K, R>) -> f64,
+    {
+        self.astar(from, to, cost, |_, _| 0.0)
+    }
+
+    /// Like `dijkstra`, but explores entities in order of their cost so far plus
+    /// `heuristic(id, components)`, an estimate of the remaining cost to `to`. The result is the
+    /// cheapest path as long as the heuristic never overestimates.
+    pub fn astar<F, H>(
+        &self,
+        from: &ID,
+        to: &ID,
+        mut cost: F,
+        mut heuristic: H,
+    ) -> Option<(Vec<ID>, f64)>
+    where
+        F: FnMut(WeightedEdge<'_, ID, K, R>) -> f64,
+        H: FnMut(&ID, &Components<K>) -> f64,
+    {
+        let (from, from_components) = self.entities.get_key_value(from)?;
+        let mut nodes = vec![from];
+        let mut indices = HashMap::from([(from, 0)]);
+        let mut best = vec![0.0];
+        let mut previous: Vec<Option<usize>> = vec![None];
+        let mut frontier = BinaryHeap::from([Candidate {
+            priority: heuristic(from, from_components),
+            cost: 0.0,
+            node: 0,
+        }]);
+
+        while let Some(Candidate {
+            cost: reached,
+            node,
+            ..
+        }) = frontier.pop()
+        {
+            if reached > best[node] {
+                continue;
+            }
+            let id = nodes[node];
+            if id == to {
+                let mut path = vec![id.clone()];
+                let mut current = node;
+                while let Some(index) = previous[current] {
+                    path.push(nodes[index].clone());
+                    current = index;
+                }
+                path.reverse();
+                return Some((path, reached));
+            }
+            let from_components = &self.entities[id];
+            for (relationship, adjacency_list) in &self.relationships {
+                for neighbor in adjacency_list.edges.get(id).into_iter().flatten() {
+                    let Some(to_components) = self.entities.get(neighbor) else {
+                        continue;
+                    };
+                    let step = cost(WeightedEdge {
+                        relationship,
+                        from: id,
+                        to: neighbor,
+                        from_components,
+                        to_components,
+                        data: adjacency_list.edge_data(id, neighbor),
+                    });
+                    if !step.is_finite() {
+                        continue;
+                    }
+                    let total = reached + step;
+                    let next = *indices.entry(neighbor).or_insert_with(|| {
+                        nodes.push(neighbor);
+                        best.push(f64::INFINITY);
+                        previous.push(None);
+                        nodes.len() - 1
+                    });
+                    if total < best[next] {
+                        best[next] = total;
+                        previous[next] = Some(node);
+                        frontier.push(Candidate {
+                            priority: total + heuristic(neighbor, to_components),
+                            cost: total,
+                            node: next,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Entities in depth-first post-order. `on_cycle` is called with the cycle closed by each
+    /// back edge and returns whether to keep going.
+    fn depth_first_order<F>(&self, relationship: Option<&R>, mut on_cycle: F) -> Vec<ID>
+    where
+        F: FnMut(Vec<ID>) -> bool,
+    {
+        let relationships = relationship.map(std::slice::from_ref);
+        let mut visits = HashMap::with_capacity(self.entities.len());
+        let mut order = Vec::with_capacity(self.entities.len());
+
+        for root in self.entities.keys() {
+            if visits.contains_key(root) {
+                continue;
+            }
+            visits.insert(root, Visit::InProgress);
+            let mut stack = vec![(root, self.filtered_neighbors(root, relationships), 0)];
+            while let Some((id, neighbors, index)) = stack.last_mut() {
+                let Some(&neighbor) = neighbors.get(*index) else {
+                    visits.insert(*id, Visit::Done);
+                    order.push((*id).clone());
+                    stack.pop();
+                    continue;
+                };
+                *index += 1;
+                match visits.get(neighbor) {
+                    Some(Visit::Done) => {}
+                    Some(Visit::InProgress) => {
+                        let start = stack
+                            .iter()
+                            .position(|(id, _, _)| *id == neighbor)
+                            .unwrap_or_default();
+                        let mut cycle = stack[start..]
+                            .iter()
+                            .map(|(id, _, _)| (*id).clone())
+                            .collect::<Vec<_>>();
+                        cycle.push(neighbor.clone());
+                        if !on_cycle(cycle) {
+                            return order;
+                        }
+                    }
+                    None => {
+                        visits.insert(neighbor, Visit::InProgress);
+                        let next = self.filtered_neighbors(neighbor, relationships);
+                        stack.push((neighbor, next, 0));
+                    }
+                }
+            }
+        }
+        order
+    }
+}
+
+impl Description {
+    /// A path with the fewest `edge_name` edges from the node called `from` to the one called
+    /// `to`, including both ends, or `None` if `to` can't be reached.
+    pub fn find_path(
+        &self,
+        from: &str,
+        to: &str,
+        edge_name: &str,
+    ) -> Result<Option<Vec<String>>, Error> {
+        let from_entity = self.entity_of(from).ok_or_else(|| Error::NodeNotFound {
+            name: from.to_string(),
+        })?;
+        let to_entity = self.entity_of(to).ok_or_else(|| Error::NodeNotFound {
+            name: to.to_string(),
+        })?;
+        let graph = self
+            .graphs
+            .get(edge_name)
+            .ok_or_else(|| Error::EdgeNotFound {
+                name: edge_name.to_string(),
+            })?;
+        if from == to {
+            return Ok(Some(vec![from.to_string()]));
+        }
+        let (Some(start), Some(goal)) = (
+            self.node_index(edge_name, from_entity),
+            self.node_index(edge_name, to_entity),
+        ) else {
+            return Ok(None);
+        };
+        let path = petgraph::algo::astar(graph, start, |node| node == goal, |_| 1, |_| 0);
+        Ok(path.map(|(_, path)| {
+            path.into_iter()
+                .filter_map(|node| self.name_of(graph[node]).map(str::to_string))
+                .collect()
+        }))
+    }
+
+    /// Names of the nodes in the `edge_name` graph ordered so that each edge points from an
+    /// earlier node to a later one. Nodes without `edge_name` edges aren't listed. Fails with
+    /// `CycleDetected` naming a node on a cycle if there is no such order.
+    pub fn topological_order(&self, edge_name: &str) -> Result<Vec<String>, Error> {
+        let graph = self
+            .graphs
+            .get(edge_name)
+            .ok_or_else(|| Error::EdgeNotFound {
+                name: edge_name.to_string(),
+            })?;
+        let order =
+            petgraph::algo::toposort(graph, None).map_err(|cycle| Error::CycleDetected {
+                edge_name: edge_name.to_string(),
+                name: self
+                    .name_of(graph[cycle.node_id()])
+                    .unwrap_or_default()
+                    .to_string(),
+            })?;
+        Ok(order
+            .into_iter()
+            .filter_map(|node| self.name_of(graph[node]).map(str::to_string))
+            .collect())
+    }
+
+    /// Whether the `edge_name` edges form a cycle. There is none if there are no such edges.
+    pub fn has_cycle(&self, edge_name: &str) -> bool {
+        self.graphs
+            .get(edge_name)
+            .is_some_and(petgraph::algo::is_cyclic_directed)
+    }
+
+    /// Whether the node called `to` can be reached from the one called `from` along outgoing
+    /// edges of any name.
+    pub fn is_reachable(&self, from: &str, to: &str) -> Result<bool, Error> {
+        if self.entity_of(to).is_none() {
+            return Err(Error::NodeNotFound {
+                name: to.to_string(),
+            });
+        }
+        Ok(self
+            .traverse_bfs(from, &TraversalOptions::new())?
+            .iter()
+            .any(|(name, _)| name == to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn graph(edges: &[(&str, &str, &str)]) -> TestGraph {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C", "D"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from, to) in edges {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+        graph
+    }
+
+    fn position(order: &[String], id: &str) -> usize {
+        order.iter().position(|entry| entry == id).unwrap()
+    }
+
+    #[test]
+    fn topological_sort_orders_dependencies() {
+        let graph = graph(&[
+            ("depends", "A", "B"),
+            ("depends", "B", "C"),
+            ("depends", "A", "C"),
+            ("build", "D", "A"),
+        ]);
+        let order = graph.topological_sort(None).unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position(&order, "D") < position(&order, "A"));
+        assert!(position(&order, "A") < position(&order, "B"));
+        assert!(position(&order, "B") < position(&order, "C"));
+
+        let order = graph
+            .topological_sort(Some(&"depends".to_string()))
+            .unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position(&order, "A") < position(&order, "B"));
+    }
+
+    #[test]
+    fn topological_sort_reports_cycle() {
+        let graph = graph(&[
+            ("depends", "A", "B"),
+            ("depends", "B", "C"),
+            ("build", "C", "A"),
+        ]);
+        assert!(graph.topological_sort(Some(&"depends".to_string())).is_ok());
+
+        let Err(EntityGraphError::CycleDetected(cycle)) = graph.topological_sort(None) else {
+            panic!("expected a cycle");
+        };
+        assert_eq!(cycle.len(), 4);
+        assert_eq!(cycle.first(), cycle.last());
+        for pair in cycle.windows(2) {
+            assert!(graph.get_neighbors(&pair[0]).unwrap().contains(&&pair[1]));
+        }
+    }
+
+    #[test]
+    fn find_cycles_per_relationship() {
+        let mut graph = graph(&[
+            ("child", "A", "B"),
+            ("child", "B", "A"),
+            ("child", "C", "D"),
+            ("attached", "D", "D"),
+        ]);
+        assert!(graph.has_cycle(None));
+        assert!(graph.has_cycle(Some(&"attached".to_string())));
+        assert_eq!(
+            graph.find_cycles(Some(&"attached".to_string())),
+            vec![vec!["D".to_string(), "D".to_string()]]
+        );
+
+        let cycles = graph.find_cycles(None);
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.iter().all(|cycle| cycle.first() == cycle.last()));
+
+        graph
+            .remove_edge(&"child".to_string(), &"B".to_string(), &"A".to_string())
+            .unwrap();
+        assert!(!graph.has_cycle(Some(&"child".to_string())));
+        assert!(graph.find_cycles(Some(&"child".to_string())).is_empty());
+    }
+
+    #[test]
+    fn description_paths_use_node_names() -> Result<(), Error> {
+        let mut builder = crate::DescriptionBuilder::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            builder.add_node(name.to_string(), (0_u32,))?;
+        }
+        builder.add_edge("road", "a", vec!["b", "c"])?;
+        builder.add_edge("road", "b", vec!["d"])?;
+        builder.add_edge("road", "c", vec!["d"])?;
+        builder.add_edge("rail", "d", vec!["e"])?;
+        let description = builder.build();
+
+        let path = description.find_path("a", "d", "road")?.unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!((path[0].as_str(), path[2].as_str()), ("a", "d"));
+        assert_eq!(description.find_path("a", "e", "road")?, None);
+        assert_eq!(description.find_path("d", "a", "road")?, None);
+        assert_eq!(
+            description.find_path("a", "a", "rail")?,
+            Some(vec!["a".to_string()])
+        );
+        assert!(description.find_path("a", "d", "missing").is_err());
+
+        assert!(description.is_reachable("a", "e")?);
+        assert!(!description.is_reachable("e", "a")?);
+        assert!(description.is_reachable("z", "a").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn description_topological_order() -> Result<(), Error> {
+        let mut builder = crate::DescriptionBuilder::new();
+        for name in ["app", "core", "net", "log"] {
+            builder.add_node(name.to_string(), (0_u32,))?;
+        }
+        builder.add_edge("has_module", "app", vec!["net", "log"])?;
+        builder.add_edge("has_module", "net", vec!["core"])?;
+        builder.add_edge("has_module", "log", vec!["core"])?;
+        builder.add_edge("loop", "core", vec!["app"])?;
+        builder.add_edge("loop", "app", vec!["core"])?;
+        let description = builder.build();
+
+        let order = description.topological_order("has_module")?;
+        assert_eq!(order.len(), 4);
+        assert_eq!(position(&order, "app"), 0);
+        assert_eq!(position(&order, "core"), 3);
+        assert!(!description.has_cycle("has_module"));
+        assert!(!description.has_cycle("missing"));
+
+        assert!(description.has_cycle("loop"));
+        assert!(matches!(
+            description.topological_order("loop"),
+            Err(Error::CycleDetected { .. })
+        ));
+        assert!(matches!(
+            description.topological_order("missing"),
+            Err(Error::EdgeNotFound { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn shortest_path_follows_relationship() {
+        let graph = graph(&[
+            ("road", "A", "B"),
+            ("road", "B", "C"),
+            ("road", "C", "D"),
+            ("rail", "A", "D"),
+        ]);
+        let (a, d) = ("A".to_string(), "D".to_string());
+        assert_eq!(
+            graph.shortest_path(&a, &d, None),
+            Some(vec![a.clone(), d.clone()])
+        );
+        assert_eq!(
+            graph
+                .shortest_path(&a, &d, Some(&"road".to_string()))
+                .map(|path| path.len()),
+            Some(4)
+        );
+        assert_eq!(graph.shortest_path(&d, &a, None), None);
+        assert_eq!(graph.shortest_path(&a, &a, None), Some(vec![a.clone()]));
+        assert_eq!(graph.shortest_path(&"E".to_string(), &a, None), None);
+    }
+
+    #[test]
+    fn dijkstra_uses_component_costs() {
+        let mut graph = graph(&[
+            ("road", "A", "B"),
+            ("road", "B", "D"),
+            ("road", "A", "C"),
+            ("road", "C", "D"),
+        ]);
+        for (id, toll) in [("A", 0), ("B", 5), ("C", 1), ("D", 0)] {
+            graph
+                .add_component(&id.to_string(), "toll".to_string(), Value::from(toll))
+                .unwrap();
+        }
+        let (a, d) = ("A".to_string(), "D".to_string());
+        let toll = |edge: WeightedEdge<'_, String, String, String>| {
+            1.0 + edge.to_components["toll"].as_f64().unwrap()
+        };
+        assert_eq!(
+            graph.dijkstra(&a, &d, toll),
+            Some((vec![a.clone(), "C".to_string(), d.clone()], 3.0))
+        );
+        assert_eq!(graph.dijkstra(&d, &a, toll), None);
+        assert_eq!(
+            graph.dijkstra(&a, &d, |edge| if edge.to == "C" {
+                f64::INFINITY
+            } else {
+                1.0
+            }),
+            Some((vec![a.clone(), "B".to_string(), d.clone()], 2.0))
+        );
+    }
+
+    #[test]
+    fn astar_matches_dijkstra_on_a_grid() {
+        let mut graph = crate::generators::Generator::new(0).grid(6, 6);
+        for id in 0..36 {
+            let position = vec![Value::from(id % 6), Value::from(id / 6)];
+            graph
+                .add_component(&id, "position".to_string(), Value::from(position))
+                .unwrap();
+        }
+        let manhattan = |_: &usize, components: &Components<String>| {
+            let position = components["position"].as_array().unwrap();
+            let (x, y) = (position[0].as_f64().unwrap(), position[1].as_f64().unwrap());
+            (5.0 - x).abs() + (5.0 - y).abs()
+        };
+
+        let (path, cost) = graph.astar(&0, &35, |_| 1.0, manhattan).unwrap();
+        assert_eq!(cost, 10.0);
+        assert_eq!(path.len(), 11);
+        assert_eq!(graph.dijkstra(&0, &35, |_| 1.0).unwrap().1, cost);
+        assert_eq!(graph.astar(&35, &0, |_| 1.0, manhattan), None);
+    }
+}
@@ -34,6 +34,11 @@ impl AnyMap {
             .insert(TypeId::of::<T>(), Box::new(value) as Box<dyn Any + 'static>);
     }
 
+    /// Set the value contained in the map for the type of the boxed value.
+    pub(crate) fn insert_boxed(&mut self, value: Box<dyn Any + 'static>) {
+        self.data.insert((*value).type_id(), value);
+    }
+
     /// Remove the value for the type `T` if it existed.
     pub fn remove<T: 'static>(&mut self) {
         self.data.remove(&TypeId::of::<T>());
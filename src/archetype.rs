@@ -0,0 +1,151 @@
+use crate::{Components, EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The component keys of an entity, sorted by their display form.
+    pub fn archetype_of(&self, id: &ID) -> Option<Vec<K>> {
+        self.entities.get(id).map(|components| {
+            let mut keys = components.keys().cloned().collect::<Vec<_>>();
+            keys.sort_by_cached_key(ToString::to_string);
+            keys
+        })
+    }
+
+    /// Entities grouped by their exact set of component keys, as returned by `archetype_of`.
+    /// Groups are sorted by their keys and list entities in iteration order.
+    pub fn group_by_component_set(&self) -> Vec<(Vec<K>, Vec<ID>)> {
+        let mut groups = HashMap::<Vec<K>, Vec<ID>>::new();
+        for id in self.entities.keys() {
+            let archetype = self.archetype_of(id).unwrap_or_default();
+            groups.entry(archetype).or_default().push(id.clone());
+        }
+        let mut groups = groups.into_iter().collect::<Vec<_>>();
+        groups.sort_by_cached_key(|(keys, _)| {
+            keys.iter().map(ToString::to_string).collect::<Vec<_>>()
+        });
+        groups
+    }
+
+    /// Registers a named bundle of default components for `spawn_from_archetype`, returning
+    /// the defaults it replaces. Archetypes belong to this graph value and aren't serialized.
+    pub fn register_archetype(
+        &mut self,
+        name: impl Into<String>,
+        defaults: Components<K>,
+    ) -> Option<Components<K>> {
+        self.archetypes.insert(name.into(), defaults)
+    }
+
+    pub fn archetype(&self, name: &str) -> Option<&Components<K>> {
+        self.archetypes.get(name)
+    }
+
+    /// Adds an entity with the default components of an archetype, replaced or extended by
+    /// `overrides`.
+    pub fn spawn_from_archetype(
+        &mut self,
+        name: &str,
+        id: ID,
+        overrides: Components<K>,
+    ) -> Result<(), EntityGraphError> {
+        let mut components = self.archetypes.get(name).cloned().ok_or_else(|| {
+            EntityGraphError::ArchetypeNotFound {
+                name: name.to_string(),
+            }
+        })?;
+        components.extend(overrides);
+        self.add_entity(id, components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Components, EntityGraph, EntityGraphError};
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn entities_grouped_by_component_keys() {
+        let mut graph = TestGraph::new();
+        for (id, keys) in [
+            ("lamp", vec!["power", "color"]),
+            ("motor", vec!["power"]),
+            ("led", vec!["color", "power"]),
+            ("frame", vec![]),
+        ] {
+            let components = keys
+                .into_iter()
+                .map(|key| (key.to_string(), Value::Null))
+                .collect::<Components<_>>();
+            graph.add_entity(id.to_string(), components).unwrap();
+        }
+
+        assert_eq!(
+            graph.archetype_of(&"lamp".to_string()),
+            Some(vec!["color".to_string(), "power".to_string()])
+        );
+        assert_eq!(graph.archetype_of(&"missing".to_string()), None);
+
+        let mut groups = graph.group_by_component_set();
+        for (_, ids) in &mut groups {
+            ids.sort();
+        }
+        assert_eq!(
+            groups,
+            vec![
+                (vec![], vec!["frame".to_string()]),
+                (
+                    vec!["color".to_string(), "power".to_string()],
+                    vec!["lamp".to_string(), "led".to_string()]
+                ),
+                (vec!["power".to_string()], vec!["motor".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn entities_spawned_from_archetypes() {
+        let mut graph = TestGraph::new();
+        let defaults = Components::from([
+            ("color".to_string(), Value::from("white")),
+            ("brightness".to_string(), Value::from(100)),
+        ]);
+        assert!(graph
+            .register_archetype("led_module", defaults.clone())
+            .is_none());
+
+        graph
+            .spawn_from_archetype("led_module", "led1".to_string(), Components::new())
+            .unwrap();
+        graph
+            .spawn_from_archetype(
+                "led_module",
+                "led2".to_string(),
+                Components::from([
+                    ("color".to_string(), Value::from("red")),
+                    ("blink".to_string(), Value::from(true)),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(graph.components(&"led1".to_string()), Some(&defaults));
+        let led2 = graph.components(&"led2".to_string()).unwrap();
+        assert_eq!(led2["color"], "red");
+        assert_eq!(led2["brightness"], 100);
+        assert_eq!(led2["blink"], true);
+        assert!(matches!(
+            graph.spawn_from_archetype("sensor", "sensor1".to_string(), Components::new()),
+            Err(EntityGraphError::ArchetypeNotFound { .. })
+        ));
+        assert!(matches!(
+            graph.spawn_from_archetype("led_module", "led1".to_string(), Components::new()),
+            Err(EntityGraphError::EntityAlreadyExists { .. })
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{Components, EntityGraph, EntityGraphError, LabelSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+/// Tracks which entities changed at which graph version. Every mutation bumps the version and
+/// stamps the entities it touched, so a consumer holding an older version can ask for exactly
+/// what changed since.
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeTracker<ID: Eq + Hash> {
+    version: u64,
+    entity_versions: HashMap<ID, u64>,
+    removed: HashMap<ID, u64>,
+}
+
+impl<ID: Eq + Hash> Default for ChangeTracker<ID> {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            entity_versions: HashMap::new(),
+            removed: HashMap::new(),
+        }
+    }
+}
+
+impl<ID: Eq + Hash + Clone> ChangeTracker<ID> {
+    /// A tracker where every entity in `ids` is considered changed at version 1.
+    pub(crate) fn with_entities<'a>(ids: impl Iterator<Item = &'a ID>) -> Self
+    where
+        ID: 'a,
+    {
+        Self {
+            version: 1,
+            entity_versions: ids.map(|id| (id.clone(), 1)).collect(),
+            removed: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn touch(&mut self, id: &ID) {
+        self.version += 1;
+        self.removed.remove(id);
+        self.entity_versions.insert(id.clone(), self.version);
+    }
+
+    pub(crate) fn remove(&mut self, id: &ID) {
+        self.version += 1;
+        self.entity_versions.remove(id);
+        self.removed.insert(id.clone(), self.version);
+    }
+}
+
+/// The full state of one entity, as carried by a `ChangeSet`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(bound(
+    serialize = "ID: Serialize, K: Serialize, R: Serialize",
+    deserialize = "ID: Deserialize<'de>, K: Deserialize<'de>, R: Deserialize<'de>"
+))]
+pub struct EntityChange<ID, K: Eq + Hash, R> {
+    pub id: ID,
+    pub components: Components<K>,
+    #[serde(default, skip_serializing_if = "LabelSet::is_empty")]
+    pub labels: LabelSet,
+    /// Outgoing edges, grouped by relationship.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<(R, Vec<ID>)>,
+    /// Payloads of outgoing edges, as `(relationship, target, data)`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edge_data: Vec<(R, ID, Value)>,
+}
+
+/// Everything that changed in a graph between two versions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(bound(
+    serialize = "ID: Serialize, K: Serialize, R: Serialize",
+    deserialize = "ID: Deserialize<'de>, K: Deserialize<'de>, R: Deserialize<'de>"
+))]
+pub struct ChangeSet<ID, K: Eq + Hash, R> {
+    pub since: u64,
+    pub version: u64,
+    pub changed: Vec<EntityChange<ID, K, R>>,
+    pub removed: Vec<ID>,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The current version. Pass it to `export_changes_since` later to get only newer changes.
+    pub fn version(&self) -> u64 {
+        self.changes.version
+    }
+
+    /// Every entity added or modified after version `since` (including changes to its labels or
+    /// outgoing edges), and every entity removed after it. `export_changes_since(0)` exports the
+    /// whole graph.
+    pub fn export_changes_since(&self, since: u64) -> ChangeSet<ID, K, R> {
+        let changed = self
+            .changes
+            .entity_versions
+            .iter()
+            .filter(|(_, version)| **version > since)
+            .filter_map(|(id, _)| {
+                let components = self.entities.get(id)?.clone();
+                let edges = self
+                    .relationships
+                    .iter()
+                    .filter_map(|(relationship, adjacency_list)| {
+                        let neighbors = adjacency_list.edges.get(id)?;
+                        (!neighbors.is_empty()).then(|| (relationship.clone(), neighbors.clone()))
+                    })
+                    .collect();
+                let edge_data =
+                    self.relationships
+                        .iter()
+                        .flat_map(|(relationship, adjacency_list)| {
+                            adjacency_list.data.get(id).into_iter().flatten().map(
+                                move |(to, data)| (relationship.clone(), to.clone(), data.clone()),
+                            )
+                        })
+                        .collect();
+                Some(EntityChange {
+                    id: id.clone(),
+                    components,
+                    labels: self.labels.get(id).cloned().unwrap_or_default(),
+                    edges,
+                    edge_data,
+                })
+            })
+            .collect();
+        let removed = self
+            .changes
+            .removed
+            .iter()
+            .filter(|(_, version)| **version > since)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ChangeSet {
+            since,
+            version: self.version(),
+            changed,
+            removed,
+        }
+    }
+
+    /// Applies a `ChangeSet` exported from another graph. Changed entities replace any local
+    /// entity with the same ID, including its labels and outgoing edges.
+    pub fn import_changes(&mut self, changes: ChangeSet<ID, K, R>) -> Result<(), EntityGraphError> {
+        for id in &changes.removed {
+            self.remove_entity(id);
+        }
+        for change in &changes.changed {
+            if let Some(components) = self.entities.get_mut(&change.id) {
+                *components = change.components.clone();
+                self.changes.touch(&change.id);
+            } else {
+                self.add_entity(change.id.clone(), change.components.clone())?;
+            }
+            let labels = self.labels.get(&change.id).cloned().unwrap_or_default();
+            for label in labels.difference(&change.labels) {
+                self.remove_label(&change.id, label);
+            }
+            for label in &change.labels {
+                self.add_label(&change.id, label.clone())?;
+            }
+            self.remove_outgoing_edges(&change.id);
+        }
+        for change in changes.changed {
+            for (relationship, neighbors) in change.edges {
+                for to in neighbors {
+                    self.add_edge(relationship.clone(), change.id.clone(), to)?;
+                }
+            }
+            for (relationship, to, data) in change.edge_data {
+                self.set_edge_data(&relationship, &change.id, &to, data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn entity(graph: &mut TestGraph, id: &str, value: i32) {
+        graph
+            .add_entity(
+                id.to_string(),
+                vec![("value".to_string(), Value::from(value))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn export_only_changes_since_version() {
+        let mut graph = TestGraph::new();
+        entity(&mut graph, "A", 1);
+        entity(&mut graph, "B", 2);
+        let checkpoint = graph.version();
+
+        entity(&mut graph, "C", 3);
+        graph
+            .add_edge("link".to_string(), "A".to_string(), "C".to_string())
+            .unwrap();
+        graph.remove_entity(&"B".to_string());
+
+        let changes = graph.export_changes_since(checkpoint);
+        let mut changed = changes
+            .changed
+            .iter()
+            .map(|change| change.id.as_str())
+            .collect::<Vec<_>>();
+        changed.sort();
+        assert_eq!(changed, vec!["A", "C"]);
+        assert_eq!(changes.removed, vec!["B"]);
+        assert!(graph
+            .export_changes_since(graph.version())
+            .changed
+            .is_empty());
+    }
+
+    #[test]
+    fn import_changes_replicates_graph() {
+        let mut source = TestGraph::new();
+        entity(&mut source, "A", 1);
+        entity(&mut source, "B", 2);
+        source
+            .add_edge("link".to_string(), "A".to_string(), "B".to_string())
+            .unwrap();
+
+        let mut replica = TestGraph::new();
+        replica
+            .import_changes(source.export_changes_since(0))
+            .unwrap();
+        assert_eq!(replica, source);
+
+        let checkpoint = source.version();
+        entity(&mut source, "C", 3);
+        source.add_label(&"A".to_string(), "root").unwrap();
+        source
+            .add_edge("link".to_string(), "A".to_string(), "C".to_string())
+            .unwrap();
+        source.remove_entity(&"B".to_string());
+
+        let delta = source.export_changes_since(checkpoint);
+        let json = serde_json::to_string(&delta).unwrap();
+        replica
+            .import_changes(serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert_eq!(replica, source);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{formats::node_name, Description, EntityGraph};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt::Display, fmt::Write, hash::Hash};
+
+/// Nodes by name with their components, and edges by relationship name.
+struct Module {
+    nodes: BTreeMap<String, BTreeMap<String, Value>>,
+    relationships: BTreeMap<String, Vec<(String, String)>>,
+}
+
+impl Module {
+    fn render(mut self) -> String {
+        for edges in self.relationships.values() {
+            for (from, to) in edges {
+                for name in [from, to] {
+                    self.nodes.entry(name.clone()).or_default();
+                }
+            }
+        }
+        let index = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(index, name)| (name.as_str(), index))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut out = String::from("// Generated by graphiti. Do not edit.\n\n");
+        out.push_str("pub const NODES: &[&str] = &[\n");
+        for name in self.nodes.keys() {
+            let _ = writeln!(out, "    {:?},", name);
+        }
+        out.push_str("];\n\n");
+        out.push_str("/// `(node, component, value as JSON)`.\n");
+        out.push_str("pub const COMPONENTS: &[(usize, &str, &str)] = &[\n");
+        for (name, components) in &self.nodes {
+            for (key, value) in components {
+                let _ = writeln!(
+                    out,
+                    "    ({}, {:?}, {:?}),",
+                    index[name.as_str()],
+                    key,
+                    value.to_string()
+                );
+            }
+        }
+        out.push_str("];\n");
+        for (relationship, edges) in &self.relationships {
+            let _ = write!(
+                out,
+                "\n/// `(from, to)` indices into `NODES` of the {:?} edges.\npub const {}: &[(usize, usize)] = &[\n",
+                relationship,
+                const_name(relationship)
+            );
+            for (from, to) in edges {
+                let _ = writeln!(
+                    out,
+                    "    ({}, {}),",
+                    index[from.as_str()],
+                    index[to.as_str()]
+                );
+            }
+            out.push_str("];\n");
+        }
+        out
+    }
+}
+
+/// An upper snake case identifier for a relationship, such as `HAS_MODULE` for `has-module`.
+fn const_name(relationship: &str) -> String {
+    let mut name = relationship
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "EDGES_");
+    }
+    name
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Rust source for a module of constants describing the graph, to be written by a build
+    /// script and `include!`d: `NODES` lists the entities sorted by name, `COMPONENTS` their
+    /// components, and one array per relationship, named in upper snake case, the edges as
+    /// indices into `NODES`. Edge payloads and labels are not included.
+    pub fn to_rust_module(&self) -> String {
+        let nodes = self
+            .entities
+            .iter()
+            .map(|(id, components)| {
+                let components = components
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect();
+                (node_name(id), components)
+            })
+            .collect();
+        let relationships = self
+            .relationships
+            .iter()
+            .map(|(relationship, adjacency_list)| {
+                let mut edges = adjacency_list
+                    .edges
+                    .iter()
+                    .flat_map(|(from, neighbors)| {
+                        neighbors.iter().map(|to| (node_name(from), node_name(to)))
+                    })
+                    .collect::<Vec<_>>();
+                edges.sort();
+                (relationship.to_string(), edges)
+            })
+            .collect();
+        Module {
+            nodes,
+            relationships,
+        }
+        .render()
+    }
+}
+
+impl Description {
+    /// Rust source for a module of constants describing the description, in the format of
+    /// `EntityGraph::to_rust_module` with nodes named as in the description.
+    pub fn to_rust_module(&self) -> String {
+        let document = self.to_declarative();
+        let relationships = document
+            .edges
+            .into_iter()
+            .map(|(edge_name, sources)| {
+                let edges = sources
+                    .into_iter()
+                    .flat_map(|(from, targets)| {
+                        targets.into_iter().map(move |to| (from.clone(), to))
+                    })
+                    .collect();
+                (edge_name, edges)
+            })
+            .collect();
+        Module {
+            nodes: document.nodes,
+            relationships,
+        }
+        .render()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn graph_is_written_as_constants() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([("volts".to_string(), Value::from(12))]),
+            )
+            .unwrap();
+        for led in ["led1", "led2"] {
+            graph
+                .add_entity(led.to_string(), Components::new())
+                .unwrap();
+            graph
+                .add_edge("powers".to_string(), "psu".to_string(), led.to_string())
+                .unwrap();
+        }
+        graph
+            .add_edge(
+                "daisy-chain".to_string(),
+                "led1".to_string(),
+                "led2".to_string(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            graph.to_rust_module(),
+            r#"// Generated by graphiti. Do not edit.
+
+pub const NODES: &[&str] = &[
+    "led1",
+    "led2",
+    "psu",
+];
+
+/// `(node, component, value as JSON)`.
+pub const COMPONENTS: &[(usize, &str, &str)] = &[
+    (2, "volts", "12"),
+];
+
+/// `(from, to)` indices into `NODES` of the "daisy-chain" edges.
+pub const DAISY_CHAIN: &[(usize, usize)] = &[
+    (0, 1),
+];
+
+/// `(from, to)` indices into `NODES` of the "powers" edges.
+pub const POWERS: &[(usize, usize)] = &[
+    (2, 0),
+    (2, 1),
+];
+"#
+        );
+        assert_eq!(const_name("2nd"), "EDGES_2ND");
+    }
+}
@@ -0,0 +1,88 @@
+//! Gzip-compressed graph files, written and read with `flate2`. Serialized graphs repeat the
+//! same component keys throughout, so they compress well.
+
+use crate::{EntityGraph, EntityGraphError, TypeRegistry};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs::File,
+    hash::Hash,
+    io::{BufReader, Write},
+    path::Path,
+};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Writes the serialized graph to `path` as a gzip file.
+    pub fn save_compressed(&self, path: impl AsRef<Path>) -> Result<(), EntityGraphError> {
+        let file = File::create(path)
+            .map_err(|error| EntityGraphError::SerializationError(error.to_string()))?;
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        self.serialize_to_writer(&mut encoder)?;
+        encoder
+            .finish()
+            .and_then(|mut file| file.flush())
+            .map_err(|error| EntityGraphError::SerializationError(error.to_string()))
+    }
+
+    /// Reads a gzip file written by `save_compressed`, or any gzipped serialized graph,
+    /// checking its components against `registry` like `deserialize_with_registry`. The file
+    /// is decompressed as it's read rather than into memory first.
+    pub fn load_compressed(
+        path: impl AsRef<Path>,
+        registry: &TypeRegistry,
+    ) -> Result<Self, EntityGraphError> {
+        let file = File::open(path)
+            .map_err(|error| EntityGraphError::DeserializationError(error.to_string()))?;
+        Self::deserialize_from_reader(GzDecoder::new(BufReader::new(file)), registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn compressed_round_trip() {
+        let mut graph = TestGraph::new();
+        for index in 0..200 {
+            let components = Components::from([
+                ("temperature".to_string(), Value::from(index % 7)),
+                ("description".to_string(), Value::from("machine sensor")),
+            ]);
+            graph
+                .add_entity(format!("sensor{index}"), components)
+                .unwrap();
+        }
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("temperature");
+        registry.register::<String>("description");
+
+        let path = std::env::temp_dir().join(format!("graphiti-{}.json.gz", uuid::Uuid::new_v4()));
+        graph.save_compressed(&path).unwrap();
+        let compressed = std::fs::read(&path).unwrap();
+        let loaded = TestGraph::load_compressed(&path, &registry);
+        assert_eq!(loaded.unwrap(), graph);
+        assert!(compressed.len() * 5 < graph.serialize().unwrap().len());
+
+        let mut corrupted = compressed.clone();
+        let last = corrupted.len() - 9;
+        corrupted[last] ^= 0xff;
+        std::fs::write(&path, &corrupted).unwrap();
+        let loaded_corrupted = TestGraph::load_compressed(&path, &registry);
+        std::fs::write(&path, &compressed[..compressed.len() / 2]).unwrap();
+        let loaded_truncated = TestGraph::load_compressed(&path, &registry);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded_corrupted.is_err());
+        assert!(loaded_truncated.is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! A hand-editable document form of a `Description`, so descriptions written with `describe!`
+//! can be exported, edited as data and loaded back.
+
+use crate::{
+    description::{find_or_add_node, NodeIndexMap, NodeNames},
+    serde::{deserialize_ecs, is_component_registered, ENTITY_SERIALIZER},
+    Description, EdgeWeight, Endpoint, Error, Metadata,
+};
+use legion::World;
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Nodes by name with their components keyed by registered name, and edges as edge name ->
+/// source node -> target nodes.
+///
+/// ```json
+/// {
+///   "nodes": { "motor": { "speed": 3 }, "wheel": {} },
+///   "edges": { "drives": { "motor": ["wheel"] } }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct DeclarativeDescription {
+    #[serde(default)]
+    pub nodes: BTreeMap<String, BTreeMap<String, Value>>,
+    #[serde(default)]
+    pub edges: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+impl Description {
+    /// The description as a `DeclarativeDescription`. Components whose types aren't registered
+    /// are left out, and targets are listed in the order their edges were added.
+    pub fn to_declarative(&self) -> DeclarativeDescription {
+        let nodes = self
+            .registered_components()
+            .into_iter()
+            .map(|(name, components)| {
+                let components = match components {
+                    Value::Object(components) => components.into_iter().collect(),
+                    _ => BTreeMap::new(),
+                };
+                (name.to_string(), components)
+            })
+            .collect();
+
+        let mut edges = BTreeMap::<String, BTreeMap<String, Vec<String>>>::new();
+        for (edge_name, graph) in &self.graphs {
+            let sources = edges.entry(edge_name.clone()).or_default();
+            for edge in graph.edge_references() {
+                let from = self.name_of(graph[edge.source()]).unwrap_or_default();
+                let to = self.name_of(graph[edge.target()]).unwrap_or_default();
+                sources
+                    .entry(from.to_string())
+                    .or_default()
+                    .push(to.to_string());
+            }
+        }
+
+        DeclarativeDescription {
+            nodes,
+            edges,
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Builds a description from a document in the format written by `to_declarative`. Fails
+    /// with `UnregisteredComponents` if it uses a component key that hasn't been registered.
+    pub fn from_declarative(document: DeclarativeDescription) -> Result<Self, Error> {
+        let mut missing = document
+            .nodes
+            .values()
+            .flat_map(BTreeMap::keys)
+            .filter(|key| !is_component_registered(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            missing.sort();
+            missing.dedup();
+            return Err(Error::UnregisteredComponents { keys: missing });
+        }
+
+        // Allocate an entity per node and load the components under the same entity IDs.
+        let mut allocator = World::default();
+        let mut node_names = NodeNames::default();
+        let mut entities = Map::new();
+        for (name, components) in document.nodes {
+            if name.is_empty() {
+                return Err(Error::InvalidNodeName { name });
+            }
+            let entity = allocator.push(());
+            let id = legion::serialize::set_entity_serializer(&*ENTITY_SERIALIZER, || {
+                serde_json::to_value(entity)
+            })
+            .map_err(|source| Error::DeclarativeFormat { source })?;
+            let Value::String(id) = id else {
+                unreachable!("entities serialize as strings");
+            };
+            entities.insert(id, Value::Object(components.into_iter().collect()));
+            node_names.insert(name, entity);
+        }
+        let data = legion::serialize::set_entity_serializer(&*ENTITY_SERIALIZER, || {
+            deserialize_ecs(serde_json::json!({ "entities": entities }))
+        })
+        .map_err(|source| Error::DeclarativeFormat { source })?;
+
+        let mut description = Description::default();
+        description.data = data;
+        description.node_name_to_entity = node_names;
+        description.metadata = document.metadata;
+        for (_, entity) in description.node_name_to_entity.iter() {
+            if description.data.entry(*entity).is_none() {
+                description.data.push_with_id(*entity, ());
+            }
+        }
+
+        for (edge_name, sources) in document.edges {
+            if edge_name.is_empty() {
+                return Err(Error::InvalidEdgeName { name: edge_name });
+            }
+            let graph = description.graphs.entry(edge_name.clone()).or_default();
+            let mut indices = NodeIndexMap::new();
+            for (from, targets) in sources {
+                let endpoint = |endpoint, name: &String| {
+                    description
+                        .node_name_to_entity
+                        .get(name)
+                        .copied()
+                        .ok_or_else(|| Error::EdgeEndpointNotFound {
+                            edge_name: edge_name.clone(),
+                            endpoint,
+                            name: name.clone(),
+                        })
+                };
+                let from_index =
+                    find_or_add_node(graph, &mut indices, endpoint(Endpoint::Source, &from)?);
+                for to in &targets {
+                    let to_index =
+                        find_or_add_node(graph, &mut indices, endpoint(Endpoint::Target, to)?);
+                    graph.add_edge(from_index, to_index, EdgeWeight::new(edge_name.clone()));
+                }
+            }
+        }
+        Ok(description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{register_component, DescriptionBuilder};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Speed(u32);
+
+    #[test]
+    fn declarative_round_trip() -> Result<(), Error> {
+        register_component::<Speed>("declarative_speed").unwrap();
+        let mut builder = DescriptionBuilder::new();
+        builder.add_node("motor".to_string(), (Speed(3),))?;
+        builder.add_node("wheel".to_string(), (Speed(0),))?;
+        builder.add_node("frame".to_string(), ())?;
+        builder.add_edge("drives", "motor", vec!["wheel"])?;
+        builder.add_edge("holds", "frame", vec!["motor", "wheel"])?;
+        let description = builder.build();
+
+        let document = description.to_declarative();
+        assert_eq!(
+            serde_json::to_value(&document).unwrap(),
+            serde_json::json!({
+                "nodes": {
+                    "frame": {},
+                    "motor": { "declarative_speed": 3 },
+                    "wheel": { "declarative_speed": 0 }
+                },
+                "edges": {
+                    "drives": { "motor": ["wheel"] },
+                    "holds": { "frame": ["motor", "wheel"] }
+                }
+            })
+        );
+
+        let mut edited: DeclarativeDescription =
+            serde_json::from_str(&serde_json::to_string(&document).unwrap()).unwrap();
+        edited
+            .nodes
+            .get_mut("wheel")
+            .unwrap()
+            .insert("declarative_speed".to_string(), Value::from(7));
+        let reloaded = Description::from_declarative(edited)?;
+        assert_eq!(reloaded.get_component::<Speed>("wheel"), Some(&Speed(7)));
+        assert_eq!(reloaded.get_component::<Speed>("frame"), None);
+        assert!(reloaded.entity_of("frame").is_some());
+        assert_eq!(reloaded.edge_count_between("holds", "frame", "wheel")?, 1);
+
+        assert_eq!(
+            Description::from_declarative(description.to_declarative())?.content_hash(),
+            description.content_hash()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn declarative_errors() {
+        let document = |json| serde_json::from_value::<DeclarativeDescription>(json).unwrap();
+        assert!(matches!(
+            Description::from_declarative(document(serde_json::json!({
+                "nodes": { "a": { "declarative_missing": 1 } }
+            }))),
+            Err(Error::UnregisteredComponents { .. })
+        ));
+        assert!(matches!(
+            Description::from_declarative(document(serde_json::json!({
+                "nodes": { "a": {} },
+                "edges": { "link": { "a": ["b"] } }
+            }))),
+            Err(Error::EdgeEndpointNotFound {
+                endpoint: Endpoint::Target,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{mermaid::summarize, Description};
+use std::fmt::Write;
+
+/// Escapes text for a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Description {
+    /// A Graphviz DOT digraph of the description, with nodes sorted by name and edges labelled
+    /// by edge name.
+    pub fn to_dot(&self) -> String {
+        self.dot(false)
+    }
+
+    /// Like `to_dot`, listing each node's registered components under its name.
+    pub fn to_dot_with_components(&self) -> String {
+        self.dot(true)
+    }
+
+    fn dot(&self, with_components: bool) -> String {
+        let (nodes, mut edges) = self.diagram_parts();
+        let mut out = String::from("digraph {\n");
+        for (name, components) in &nodes {
+            let mut label = escape(name);
+            if with_components {
+                for (key, value) in components {
+                    let _ = write!(label, "\\n{}: {}", escape(key), escape(&summarize(value)));
+                }
+            }
+            let _ = writeln!(out, "    \"{}\" [label=\"{}\"];", escape(name), label);
+        }
+        edges.sort();
+        for (edge_name, from, to) in &edges {
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                escape(from),
+                escape(to),
+                escape(edge_name)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DescriptionBuilder, Error};
+
+    #[test]
+    fn description_dot_digraph() -> Result<(), Error> {
+        let mut builder = DescriptionBuilder::new();
+        builder.add_node("frame".to_string(), ())?;
+        builder.add_node("front \"wheel\"".to_string(), ())?;
+        builder.add_edge("holds", "frame", vec!["front \"wheel\""])?;
+        let description = builder.build();
+        assert_eq!(
+            description.to_dot(),
+            "digraph {\n    \"frame\" [label=\"frame\"];\n    \"front \\\"wheel\\\"\" [label=\"front \\\"wheel\\\"\"];\n    \"frame\" -> \"front \\\"wheel\\\"\" [label=\"holds\"];\n}\n"
+        );
+        assert_eq!(description.to_dot_with_components(), description.to_dot());
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+//! Versioned saves. `serialize_versioned` wraps the serialized graph as
+//! `{ "format_version": N, "graph": ... }`, and `deserialize_versioned` upgrades older versions
+//! through registered migrations before reading the graph. `serialize_with_checksum` adds a
+//! checksum of the graph's text to the envelope, verified when it's read.
+
+use crate::{hash::ContentHasher, EntityGraph, EntityGraphError, TypeRegistry};
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+/// The version of the serialized graph layout written by this version of the crate. Graphs
+/// serialized without an envelope are read as version 1, which has the same layout.
+pub const FORMAT_VERSION: u32 = 1;
+
+type MigrationFn = Box<dyn Fn(Value) -> Result<Value, String>>;
+
+/// Upgrades for saves written by older versions, each taking the serialized graph of one
+/// version to the next.
+#[derive(Default)]
+pub struct FormatMigrations {
+    migrations: HashMap<u32, MigrationFn>,
+}
+
+impl FormatMigrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the upgrade from `from_version` to `from_version + 1`, replacing any
+    /// registered before.
+    pub fn register_migration(
+        &mut self,
+        from_version: u32,
+        migration: impl Fn(Value) -> Result<Value, String> + 'static,
+    ) -> &mut Self {
+        self.migrations.insert(from_version, Box::new(migration));
+        self
+    }
+
+    /// Upgrades a serialized graph from `version` to `FORMAT_VERSION`.
+    pub(crate) fn migrate(
+        &self,
+        mut graph: Value,
+        mut version: u32,
+    ) -> Result<Value, EntityGraphError> {
+        if version > FORMAT_VERSION {
+            return Err(EntityGraphError::DeserializationError(format!(
+                "Format version {} is newer than this version of the crate ({})",
+                version, FORMAT_VERSION
+            )));
+        }
+        while version < FORMAT_VERSION {
+            let migration = self.migrations.get(&version).ok_or_else(|| {
+                EntityGraphError::DeserializationError(format!(
+                    "No migration registered from format version {}",
+                    version
+                ))
+            })?;
+            graph = migration(graph).map_err(|error| {
+                EntityGraphError::DeserializationError(format!(
+                    "Migration from format version {} failed: {}",
+                    version, error
+                ))
+            })?;
+            version += 1;
+        }
+        Ok(graph)
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    format_version: u32,
+    #[serde(default)]
+    checksum: Option<String>,
+    #[serde(borrow)]
+    graph: &'a RawValue,
+}
+
+/// The checksum of a serialized graph's text.
+fn checksum(graph: &str) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write(graph.as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+/// Whether `data` is an envelope rather than a bare graph: an object whose first key is
+/// `format_version`.
+pub(crate) fn is_envelope(data: &str) -> bool {
+    data.trim_start()
+        .strip_prefix('{')
+        .is_some_and(|rest| rest.trim_start().starts_with("\"format_version\""))
+}
+
+/// Reads an envelope's version and graph text, verifying its checksum if it has one.
+pub(crate) fn open(data: &str) -> Result<(u32, &str), EntityGraphError> {
+    let envelope: Envelope = serde_json::from_str(data).map_err(|e| {
+        if e.is_eof() {
+            EntityGraphError::IntegrityError("the data ends partway through the graph".into())
+        } else {
+            EntityGraphError::DeserializationError(format!("Invalid envelope: {}", e))
+        }
+    })?;
+    let graph = envelope.graph.get();
+    if let Some(expected) = envelope.checksum {
+        let actual = checksum(graph);
+        if expected != actual {
+            return Err(EntityGraphError::IntegrityError(format!(
+                "checksum {} doesn't match the graph's {}",
+                expected, actual
+            )));
+        }
+    }
+    Ok((envelope.format_version, graph))
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    fn envelope(&self, with_checksum: bool) -> Result<String, EntityGraphError> {
+        let graph = serde_json::to_string(self)
+            .map_err(|error| EntityGraphError::SerializationError(error.to_string()))?;
+        let checksum = if with_checksum {
+            format!("\"checksum\":\"{}\",", checksum(&graph))
+        } else {
+            String::new()
+        };
+        Ok(format!(
+            "{{\"format_version\":{},{}\"graph\":{}}}",
+            FORMAT_VERSION, checksum, graph
+        ))
+    }
+
+    /// Like `serialize`, wrapped in an envelope recording `FORMAT_VERSION`.
+    pub fn serialize_versioned(&self) -> Result<String, EntityGraphError> {
+        self.envelope(false)
+    }
+
+    /// Like `serialize_versioned`, with a checksum of the graph in the envelope.
+    /// `deserialize_with_registry` and `deserialize_versioned` verify it, failing with
+    /// `EntityGraphError::IntegrityError` if the graph was truncated or altered.
+    pub fn serialize_with_checksum(&self) -> Result<String, EntityGraphError> {
+        self.envelope(true)
+    }
+
+    /// Reads a graph written by `serialize_versioned` with any earlier format version,
+    /// upgrading it with `migrations`, or one written by `serialize`. Components are checked
+    /// against `registry` like `deserialize_with_registry`.
+    pub fn deserialize_versioned(
+        data: &str,
+        registry: &TypeRegistry,
+        migrations: &FormatMigrations,
+    ) -> Result<Self, EntityGraphError> {
+        let (version, graph) = if is_envelope(data) {
+            open(data)?
+        } else {
+            (FORMAT_VERSION, data)
+        };
+        if version == FORMAT_VERSION {
+            return Self::deserialize_with_registry(graph, registry);
+        }
+        let value = serde_json::from_str(graph).map_err(|e| {
+            EntityGraphError::DeserializationError(format!("Failed to deserialize graph: {}", e))
+        })?;
+        Self::from_value_with_registry(migrations.migrate(value, version)?, registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::json;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn versioned_saves_are_migrated() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([("brightness".to_string(), Value::from(3))]),
+            )
+            .unwrap();
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("brightness");
+        let migrations = FormatMigrations::new();
+
+        let saved = graph.serialize_versioned().unwrap();
+        assert_eq!(
+            TestGraph::deserialize_versioned(&saved, &registry, &migrations).unwrap(),
+            graph
+        );
+        let bare = graph.serialize().unwrap();
+        assert_eq!(
+            TestGraph::deserialize_versioned(&bare, &registry, &migrations).unwrap(),
+            graph
+        );
+
+        // A save from before entities were stored under "entities".
+        let old =
+            r#"{ "format_version": 0, "graph": { "nodes": { "led": { "brightness": 3 } } } }"#;
+        assert!(TestGraph::deserialize_versioned(old, &registry, &migrations).is_err());
+        let mut migrations = FormatMigrations::new();
+        migrations.register_migration(0, |graph| {
+            let nodes = graph.get("nodes").cloned().ok_or("no nodes")?;
+            Ok(json!({ "entities": nodes, "relationships": {} }))
+        });
+        assert_eq!(
+            TestGraph::deserialize_versioned(old, &registry, &migrations).unwrap(),
+            graph
+        );
+
+        let future = format!(
+            r#"{{ "format_version": {}, "graph": {{}} }}"#,
+            FORMAT_VERSION + 1
+        );
+        assert!(TestGraph::deserialize_versioned(&future, &registry, &migrations).is_err());
+    }
+
+    #[test]
+    fn checksums_are_verified() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([("brightness".to_string(), Value::from(3))]),
+            )
+            .unwrap();
+        graph
+            .add_entity("psu".to_string(), Components::new())
+            .unwrap();
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("brightness");
+
+        let saved = graph.serialize_with_checksum().unwrap();
+        assert_eq!(
+            TestGraph::deserialize_with_registry(&saved, &registry).unwrap(),
+            graph
+        );
+        assert_eq!(
+            TestGraph::deserialize_versioned(&saved, &registry, &FormatMigrations::new()).unwrap(),
+            graph
+        );
+
+        let altered = saved.replace("\"brightness\":3", "\"brightness\":4");
+        assert!(matches!(
+            TestGraph::deserialize_with_registry(&altered, &registry),
+            Err(EntityGraphError::IntegrityError(_))
+        ));
+        assert!(matches!(
+            TestGraph::deserialize_with_registry(&saved[..saved.len() - 20], &registry),
+            Err(EntityGraphError::IntegrityError(_))
+        ));
+        let unchecked = graph.serialize_versioned().unwrap();
+        assert_eq!(
+            TestGraph::deserialize_with_registry(&unchecked, &registry).unwrap(),
+            graph
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use super::{edge_list, node_list, node_name, parse_name};
+use crate::{Components, EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    hash::Hash,
+    io::{Read, Write},
+};
+
+const EDGE_HEADER: [&str; 4] = ["relationship", "from", "to", "data"];
+
+fn write_error(error: impl Display) -> EntityGraphError {
+    EntityGraphError::SerializationError(error.to_string())
+}
+
+fn invalid(message: impl Display) -> EntityGraphError {
+    EntityGraphError::DeserializationError(format!("Invalid CSV: {}", message))
+}
+
+/// A cell as a value, or `None` for an empty cell.
+fn cell_value(cell: &str) -> Result<Option<Value>, EntityGraphError> {
+    if cell.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(cell).map(Some).map_err(invalid)
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Writes the graph as two CSV tables. The node table has an `id` column and a column per
+    /// component key, each cell holding the component's value as JSON, or nothing if the
+    /// entity doesn't have it. The edge table has `relationship`, `from`, `to` and `data`
+    /// columns, the last holding edge payloads as JSON.
+    pub fn to_csv(
+        &self,
+        nodes_writer: impl Write,
+        edges_writer: impl Write,
+    ) -> Result<(), EntityGraphError> {
+        let nodes = node_list(self);
+        let columns = nodes
+            .iter()
+            .flat_map(|(_, _, components)| components.keys())
+            .map(|key| (node_name(key), key))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut writer = ::csv::Writer::from_writer(nodes_writer);
+        writer
+            .write_record(
+                std::iter::once("id").chain(columns.iter().map(|(name, _)| name.as_str())),
+            )
+            .map_err(write_error)?;
+        for (name, _, components) in &nodes {
+            let cells = columns.iter().map(|(_, key)| {
+                components
+                    .get(*key)
+                    .map(Value::to_string)
+                    .unwrap_or_default()
+            });
+            writer
+                .write_record(std::iter::once(name.clone()).chain(cells))
+                .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)?;
+
+        let mut writer = ::csv::Writer::from_writer(edges_writer);
+        writer.write_record(EDGE_HEADER).map_err(write_error)?;
+        for (relationship, from, to, data) in edge_list(self) {
+            let data = data.map(Value::to_string).unwrap_or_default();
+            writer
+                .write_record([&relationship, &from, &to, &data])
+                .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)
+    }
+
+    /// Reads the tables written by `to_csv`. Empty cells are missing components and every
+    /// other cell must hold JSON.
+    pub fn from_csv(
+        nodes_reader: impl Read,
+        edges_reader: impl Read,
+    ) -> Result<Self, EntityGraphError> {
+        let mut graph = Self::new();
+        let mut nodes = ::csv::Reader::from_reader(nodes_reader);
+        let header = nodes.headers().map_err(invalid)?.clone();
+        if header.get(0) != Some("id") {
+            return Err(invalid("the node table's first column isn't 'id'"));
+        }
+        let keys = header
+            .iter()
+            .skip(1)
+            .map(|column| {
+                parse_name::<K>(column)
+                    .ok_or_else(|| invalid(format!("invalid component key '{}'", column)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for record in nodes.records() {
+            let record = record.map_err(invalid)?;
+            let mut cells = record.iter();
+            let name = cells.next().ok_or_else(|| invalid("empty node row"))?;
+            let id =
+                parse_name::<ID>(name).ok_or_else(|| invalid(format!("invalid ID '{}'", name)))?;
+            let mut components = Components::new();
+            for (key, cell) in keys.iter().zip(cells) {
+                if let Some(value) = cell_value(cell)? {
+                    components.insert(key.clone(), value);
+                }
+            }
+            graph.add_entity(id, components)?;
+        }
+
+        let mut edges = ::csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(edges_reader);
+        let header = edges.headers().map_err(invalid)?;
+        if header.len() < 3 || header.iter().take(3).ne(EDGE_HEADER[..3].iter().copied()) {
+            return Err(invalid(
+                "the edge table doesn't start with relationship,from,to",
+            ));
+        }
+        for record in edges.records() {
+            let record = record.map_err(invalid)?;
+            let (Some(relationship), Some(from), Some(to)) =
+                (record.get(0), record.get(1), record.get(2))
+            else {
+                return Err(invalid("edge row with fewer than three columns"));
+            };
+            let data = cell_value(record.get(3).unwrap_or_default())?;
+            let parse_id = |name: &str| {
+                parse_name::<ID>(name).ok_or_else(|| invalid(format!("invalid ID '{}'", name)))
+            };
+            let relationship = parse_name::<R>(relationship)
+                .ok_or_else(|| invalid(format!("invalid relationship '{}'", relationship)))?;
+            graph.add_edge_with_optional_data(
+                relationship,
+                parse_id(from)?,
+                parse_id(to)?,
+                data,
+            )?;
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn csv_round_trip() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(12)),
+                    ("name".to_string(), Value::from("Main, backup")),
+                    ("spec".to_string(), json!({ "phases": 3, "ports": [1, 2] })),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([("volts".to_string(), Value::from(5))]),
+            )
+            .unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                json!({ "amps": 0.5 }),
+            )
+            .unwrap();
+        graph
+            .add_edge("monitors".to_string(), "led".to_string(), "psu".to_string())
+            .unwrap();
+
+        let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+        graph.to_csv(&mut nodes, &mut edges).unwrap();
+        assert_eq!(
+            String::from_utf8(nodes.clone()).unwrap(),
+            "id,name,spec,volts\nled,,,5\npsu,\"\"\"Main, backup\"\"\",\"{\"\"phases\"\":3,\"\"ports\"\":[1,2]}\",12\n"
+        );
+        assert_eq!(
+            String::from_utf8(edges.clone()).unwrap(),
+            "relationship,from,to,data\nmonitors,led,psu,\npowers,psu,led,\"{\"\"amps\"\":0.5}\"\n"
+        );
+        assert_eq!(
+            TestGraph::from_csv(nodes.as_slice(), edges.as_slice()).unwrap(),
+            graph
+        );
+        assert!(TestGraph::from_csv("name\n".as_bytes(), edges.as_slice()).is_err());
+    }
+
+    #[test]
+    fn csv_keeps_strings_that_look_like_other_values() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "sensor".to_string(),
+                Components::from([
+                    ("count".to_string(), Value::from("42")),
+                    ("enabled".to_string(), Value::from("true")),
+                    ("note".to_string(), Value::from("")),
+                    ("unit.name".to_string(), Value::from("volts")),
+                    ("reading".to_string(), Value::Null),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "probe".to_string(),
+                Components::from([("count".to_string(), Value::from(42))]),
+            )
+            .unwrap();
+
+        let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+        graph.to_csv(&mut nodes, &mut edges).unwrap();
+        assert_eq!(
+            TestGraph::from_csv(nodes.as_slice(), edges.as_slice()).unwrap(),
+            graph
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use super::{edge_list, node_list, node_name};
+use crate::EntityGraph;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{fmt::Display, hash::Hash};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The graph in the Cytoscape.js elements format, `{ "nodes": [...], "edges": [...] }`,
+    /// ready to pass to `cytoscape({ elements })`. A node's `data` holds its `id` and its
+    /// components, and its labels become its `classes`. An edge's `data` holds its `id`,
+    /// `source`, `target`, `relationship` and any payload as `payload`.
+    pub fn to_cytoscape(&self) -> Value {
+        let nodes = node_list(self)
+            .into_iter()
+            .map(|(name, id, components)| {
+                let mut data = components
+                    .iter()
+                    .map(|(key, value)| (node_name(key), value.clone()))
+                    .collect::<Map<_, _>>();
+                data.insert("id".to_string(), Value::String(name));
+                let mut node = json!({ "data": data });
+                if let Some(labels) = self.labels.get(id).filter(|labels| !labels.is_empty()) {
+                    let mut labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+                    labels.sort_unstable();
+                    node["classes"] = Value::from(labels.join(" "));
+                }
+                node
+            })
+            .collect::<Vec<_>>();
+        let edges = edge_list(self)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (relationship, from, to, payload))| {
+                let mut data = json!({
+                    "id": format!("e{index}"),
+                    "source": from,
+                    "target": to,
+                    "relationship": relationship,
+                });
+                if let Some(payload) = payload {
+                    data["payload"] = payload.clone();
+                }
+                json!({ "data": data })
+            })
+            .collect::<Vec<_>>();
+        json!({ "nodes": nodes, "edges": edges })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn cytoscape_elements() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([("volts".to_string(), Value::from(12))]),
+            )
+            .unwrap();
+        graph
+            .add_entity("led".to_string(), Components::new())
+            .unwrap();
+        graph.add_label(&"led".to_string(), "output").unwrap();
+        graph.add_label(&"led".to_string(), "light").unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                json!({ "amps": 0.5 }),
+            )
+            .unwrap();
+
+        assert_eq!(
+            graph.to_cytoscape(),
+            json!({
+                "nodes": [
+                    { "data": { "id": "led" }, "classes": "light output" },
+                    { "data": { "id": "psu", "volts": 12 } }
+                ],
+                "edges": [
+                    {
+                        "data": {
+                            "id": "e0",
+                            "source": "psu",
+                            "target": "led",
+                            "relationship": "powers",
+                            "payload": { "amps": 0.5 }
+                        }
+                    }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use super::{attribute_type, edge_list, node_list, node_name, value_text, xml};
+use crate::EntityGraph;
+use quick_xml::Writer;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt::Display, hash::Hash, io};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The graph as a GEXF 1.3 document for Gephi. Each component key becomes a node
+    /// attribute, typed as in `to_graphml`, and each edge's relationship is its `kind` and
+    /// label, so relationships can be told apart in Gephi. Numeric edge payloads are used as
+    /// the edge weight.
+    pub fn to_gexf(&self) -> String {
+        let mut attributes = BTreeMap::<String, Vec<&Value>>::new();
+        for components in self.entities.values() {
+            for (key, value) in components {
+                attributes.entry(node_name(key)).or_default().push(value);
+            }
+        }
+        let attributes = attributes
+            .into_iter()
+            .enumerate()
+            .map(|(index, (title, values))| {
+                let (attribute_type, json) = attribute_type(values.into_iter());
+                (title, (index.to_string(), attribute_type, json))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        xml::document(|writer| {
+            writer
+                .create_element("gexf")
+                .with_attributes([("xmlns", "http://gexf.net/1.3"), ("version", "1.3")])
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("graph")
+                        .with_attributes([("defaultedgetype", "directed"), ("mode", "static")])
+                        .write_inner_content(|writer| {
+                            writer
+                                .create_element("attributes")
+                                .with_attribute(("class", "node"))
+                                .write_inner_content(|writer| {
+                                    for (title, (id, attribute_type, _)) in &attributes {
+                                        writer
+                                            .create_element("attribute")
+                                            .with_attributes([
+                                                ("id", id.as_str()),
+                                                ("title", title),
+                                                ("type", attribute_type),
+                                            ])
+                                            .write_empty()?;
+                                    }
+                                    Ok(())
+                                })?;
+                            writer
+                                .create_element("nodes")
+                                .write_inner_content(|writer| {
+                                    self.write_gexf_nodes(writer, &attributes)
+                                })?;
+                            writer
+                                .create_element("edges")
+                                .write_inner_content(|writer| self.write_gexf_edges(writer))?;
+                            Ok(())
+                        })?;
+                    Ok(())
+                })?;
+            Ok(())
+        })
+    }
+
+    fn write_gexf_nodes(
+        &self,
+        writer: &mut Writer<Vec<u8>>,
+        attributes: &BTreeMap<String, (String, &str, bool)>,
+    ) -> io::Result<()> {
+        for (name, _, components) in node_list(self) {
+            let node = writer
+                .create_element("node")
+                .with_attributes([("id", name.as_str()), ("label", name.as_str())]);
+            if components.is_empty() {
+                node.write_empty()?;
+                continue;
+            }
+            let mut components = components
+                .iter()
+                .map(|(key, value)| (node_name(key), value))
+                .collect::<Vec<_>>();
+            components.sort_by(|(a, _), (b, _)| a.cmp(b));
+            node.write_inner_content(|writer| {
+                writer
+                    .create_element("attvalues")
+                    .write_inner_content(|writer| {
+                        for (key, value) in components {
+                            let (id, _, json) = &attributes[&key];
+                            writer
+                                .create_element("attvalue")
+                                .with_attributes([
+                                    ("for", id.as_str()),
+                                    ("value", &value_text(value, *json)),
+                                ])
+                                .write_empty()?;
+                        }
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn write_gexf_edges(&self, writer: &mut Writer<Vec<u8>>) -> io::Result<()> {
+        for (index, (relationship, from, to, data)) in edge_list(self).into_iter().enumerate() {
+            let id = index.to_string();
+            let weight = data
+                .and_then(Value::as_f64)
+                .map(|weight| weight.to_string());
+            let mut attributes = vec![
+                ("id", id.as_str()),
+                ("source", from.as_str()),
+                ("target", to.as_str()),
+                ("kind", relationship.as_str()),
+                ("label", relationship.as_str()),
+            ];
+            if let Some(weight) = &weight {
+                attributes.push(("weight", weight));
+            }
+            writer
+                .create_element("edge")
+                .with_attributes(attributes)
+                .write_empty()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn gexf_export() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(12)),
+                    ("name".to_string(), Value::from("Main & backup")),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity("led".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                Value::from(0.5),
+            )
+            .unwrap();
+        graph
+            .add_edge("monitors".to_string(), "led".to_string(), "psu".to_string())
+            .unwrap();
+
+        assert_eq!(
+            graph.to_gexf(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" version="1.3">
+  <graph defaultedgetype="directed" mode="static">
+    <attributes class="node">
+      <attribute id="0" title="name" type="string"/>
+      <attribute id="1" title="volts" type="long"/>
+    </attributes>
+    <nodes>
+      <node id="led" label="led"/>
+      <node id="psu" label="psu">
+        <attvalues>
+          <attvalue for="0" value="Main &amp; backup"/>
+          <attvalue for="1" value="12"/>
+        </attvalues>
+      </node>
+    </nodes>
+    <edges>
+      <edge id="0" source="led" target="psu" kind="monitors" label="monitors"/>
+      <edge id="1" source="psu" target="led" kind="powers" label="powers" weight="0.5"/>
+    </edges>
+  </graph>
+</gexf>
+"#
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use super::{attribute_type, edge_list, node_list, node_name, parse_name, value_text, xml};
+use crate::{Components, EntityGraph, EntityGraphError};
+use quick_xml::{events::BytesText, Writer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    hash::Hash,
+    io,
+};
+
+// Keys written for graph structure rather than components.
+const LABELS_KEY: &str = "labels";
+const RELATIONSHIP_KEY: &str = "relationship";
+const EDGE_DATA_KEY: &str = "edge_data";
+// Relationship given to imported edges that don't name one.
+const DEFAULT_RELATIONSHIP: &str = "edge";
+// Marks keys whose values are written as JSON text.
+const JSON_DESCRIPTION: &str = "json";
+
+fn invalid(message: impl Display) -> EntityGraphError {
+    EntityGraphError::DeserializationError(format!("Invalid GraphML: {}", message))
+}
+
+fn parse_value(text: &str, attribute_type: &str, json: bool) -> Result<Value, EntityGraphError> {
+    if json {
+        return serde_json::from_str(text).map_err(invalid);
+    }
+    let trimmed = text.trim();
+    Ok(match attribute_type {
+        "boolean" => Value::Bool(trimmed.eq_ignore_ascii_case("true")),
+        "int" | "long" => Value::from(trimmed.parse::<i64>().map_err(invalid)?),
+        "float" | "double" => Value::from(trimmed.parse::<f64>().map_err(invalid)?),
+        _ => Value::String(text.to_string()),
+    })
+}
+
+/// The component key declarations, mapped from key name to id, type and whether values are
+/// JSON text, followed by the keys for labels, relationships and edge payloads.
+fn write_keys(
+    writer: &mut Writer<Vec<u8>>,
+    keys: &BTreeMap<String, (String, &str, bool)>,
+) -> io::Result<()> {
+    let structure_keys = [
+        (LABELS_KEY, "node", true),
+        (RELATIONSHIP_KEY, "edge", false),
+        (EDGE_DATA_KEY, "edge", true),
+    ];
+    let declarations = keys
+        .iter()
+        .map(|(name, (id, attribute_type, json))| {
+            (id.as_str(), "node", name.as_str(), *attribute_type, *json)
+        })
+        .chain(
+            structure_keys
+                .into_iter()
+                .map(|(id, target, json)| (id, target, id, "string", json)),
+        );
+    for (id, target, name, attribute_type, json) in declarations {
+        let key = writer.create_element("key").with_attributes([
+            ("id", id),
+            ("for", target),
+            ("attr.name", name),
+            ("attr.type", attribute_type),
+        ]);
+        if json {
+            key.write_inner_content(|writer| {
+                writer
+                    .create_element("desc")
+                    .write_text_content(BytesText::new(JSON_DESCRIPTION))?;
+                Ok(())
+            })?;
+        } else {
+            key.write_empty()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_data(writer: &mut Writer<Vec<u8>>, key: &str, text: &str) -> io::Result<()> {
+    writer
+        .create_element("data")
+        .with_attribute(("key", key))
+        .write_text_content(BytesText::new(text))?;
+    Ok(())
+}
+
+struct Key<'a> {
+    name: &'a str,
+    attribute_type: &'a str,
+    json: bool,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The graph as a GraphML document. Each component key becomes a node `<key>`, typed when
+    /// all its values are strings, booleans or numbers and written as JSON text otherwise.
+    /// Edges carry their relationship name, and payload if they have one, as `<data>`.
+    pub fn to_graphml(&self) -> String {
+        let mut keys = BTreeMap::<String, Vec<&Value>>::new();
+        for components in self.entities.values() {
+            for (key, value) in components {
+                keys.entry(node_name(key)).or_default().push(value);
+            }
+        }
+        let keys = keys
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, values))| {
+                let (attribute_type, json) = attribute_type(values.into_iter());
+                (name, (format!("c{index}"), attribute_type, json))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        xml::document(|writer| {
+            writer
+                .create_element("graphml")
+                .with_attribute(("xmlns", "http://graphml.graphdrawing.org/xmlns"))
+                .write_inner_content(|writer| {
+                    write_keys(writer, &keys)?;
+                    writer
+                        .create_element("graph")
+                        .with_attributes([("id", "G"), ("edgedefault", "directed")])
+                        .write_inner_content(|writer| {
+                            self.write_graphml_nodes(writer, &keys)?;
+                            self.write_graphml_edges(writer)
+                        })?;
+                    Ok(())
+                })?;
+            Ok(())
+        })
+    }
+
+    fn write_graphml_edges(&self, writer: &mut Writer<Vec<u8>>) -> io::Result<()> {
+        for (relationship, from, to, data) in edge_list(self) {
+            writer
+                .create_element("edge")
+                .with_attributes([("source", from.as_str()), ("target", to.as_str())])
+                .write_inner_content(|writer| {
+                    write_data(writer, RELATIONSHIP_KEY, &relationship)?;
+                    if let Some(data) = data {
+                        write_data(writer, EDGE_DATA_KEY, &data.to_string())?;
+                    }
+                    Ok(())
+                })?;
+        }
+        Ok(())
+    }
+
+    fn write_graphml_nodes(
+        &self,
+        writer: &mut Writer<Vec<u8>>,
+        keys: &BTreeMap<String, (String, &str, bool)>,
+    ) -> io::Result<()> {
+        for (name, id, components) in node_list(self) {
+            writer
+                .create_element("node")
+                .with_attribute(("id", name.as_str()))
+                .write_inner_content(|writer| {
+                    let mut components = components
+                        .iter()
+                        .map(|(key, value)| (node_name(key), value))
+                        .collect::<Vec<_>>();
+                    components.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (key, value) in components {
+                        let (key_id, _, json) = &keys[&key];
+                        write_data(writer, key_id, &value_text(value, *json))?;
+                    }
+                    if let Some(labels) = self.labels.get(id).filter(|labels| !labels.is_empty()) {
+                        let mut labels = labels.iter().collect::<Vec<_>>();
+                        labels.sort();
+                        write_data(
+                            writer,
+                            LABELS_KEY,
+                            &serde_json::to_string(&labels).unwrap_or_default(),
+                        )?;
+                    }
+                    Ok(())
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Reads a GraphML document, as written by `to_graphml` or another tool. Node `<data>`
+    /// becomes components named by their key's `attr.name`, and edges without a relationship
+    /// are given the relationship `edge`. Keys with a `yfiles.type` hold drawing information
+    /// and are skipped.
+    pub fn from_graphml(input: &str) -> Result<Self, EntityGraphError> {
+        let root = xml::parse(input).map_err(invalid)?;
+        if root.local_name() != "graphml" {
+            return Err(invalid("the root element isn't <graphml>"));
+        }
+        let keys = root
+            .children_named("key")
+            .filter(|key| key.attribute("yfiles.type").is_none())
+            .filter_map(|key| {
+                let id = key.attribute("id")?;
+                let name = key.attribute("attr.name").unwrap_or(id);
+                let json = key
+                    .child("desc")
+                    .is_some_and(|desc| desc.text.trim() == JSON_DESCRIPTION);
+                Some((
+                    id,
+                    Key {
+                        name,
+                        attribute_type: key.attribute("attr.type").unwrap_or("string"),
+                        json,
+                    },
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+        let graph_element = root
+            .child("graph")
+            .ok_or_else(|| invalid("no <graph> element"))?;
+
+        let parse_id = |name: &str| {
+            parse_name::<ID>(name).ok_or_else(|| invalid(format!("invalid ID '{}'", name)))
+        };
+        let mut graph = Self::new();
+        let mut labels = Vec::new();
+        for node in graph_element.children_named("node") {
+            let id = parse_id(
+                node.attribute("id")
+                    .ok_or_else(|| invalid("node without an id"))?,
+            )?;
+            let mut components = Components::new();
+            for data in node.children_named("data") {
+                let Some(key_id) = data.attribute("key") else {
+                    continue;
+                };
+                if key_id == LABELS_KEY {
+                    let names: Vec<String> = serde_json::from_str(&data.text).map_err(invalid)?;
+                    labels.push((id.clone(), names));
+                    continue;
+                }
+                let Some(key) = keys.get(key_id) else {
+                    continue;
+                };
+                let component = parse_name::<K>(key.name)
+                    .ok_or_else(|| invalid(format!("invalid component key '{}'", key.name)))?;
+                components.insert(
+                    component,
+                    parse_value(&data.text, key.attribute_type, key.json)?,
+                );
+            }
+            graph.add_entity(id, components)?;
+        }
+        for (id, names) in labels {
+            for label in names {
+                graph.add_label(&id, label)?;
+            }
+        }
+        for edge in graph_element.children_named("edge") {
+            let endpoint = |name| {
+                parse_id(
+                    edge.attribute(name)
+                        .ok_or_else(|| invalid(format!("edge without a {}", name)))?,
+                )
+            };
+            let (from, to) = (endpoint("source")?, endpoint("target")?);
+            let mut relationship = DEFAULT_RELATIONSHIP.to_string();
+            let mut data = None;
+            for element in edge.children_named("data") {
+                match element.attribute("key") {
+                    Some(RELATIONSHIP_KEY) => relationship = element.text.clone(),
+                    Some(EDGE_DATA_KEY) => {
+                        data = Some(serde_json::from_str(&element.text).map_err(invalid)?)
+                    }
+                    _ => {}
+                }
+            }
+            let relationship = parse_name::<R>(&relationship)
+                .ok_or_else(|| invalid(format!("invalid relationship '{}'", relationship)))?;
+            graph.add_edge_with_optional_data(relationship, from, to, data)?;
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn graphml_round_trip() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(12)),
+                    ("name".to_string(), Value::from("Main <PSU>")),
+                    ("spec".to_string(), json!({ "phases": 3 })),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(5)),
+                    ("spec".to_string(), Value::from("none")),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                json!({ "amps": 0.5 }),
+            )
+            .unwrap();
+        graph.add_label(&"led".to_string(), "output").unwrap();
+
+        let graphml = graph.to_graphml();
+        assert!(graphml
+            .contains("<key id=\"c2\" for=\"node\" attr.name=\"volts\" attr.type=\"long\"/>"));
+        assert!(graphml.contains("<data key=\"c0\">Main &lt;PSU&gt;</data>"));
+        assert_eq!(TestGraph::from_graphml(&graphml).unwrap(), graph);
+        assert_eq!(
+            TestGraph::from_graphml(&graphml).unwrap().get_edge_data(
+                &"powers".to_string(),
+                &"psu".to_string(),
+                &"led".to_string()
+            ),
+            Some(&json!({ "amps": 0.5 }))
+        );
+    }
+
+    #[test]
+    fn graphml_from_other_tools() {
+        let graph = TestGraph::from_graphml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:y="http://www.yworks.com/xml/graphml">
+              <key id="d0" for="node" attr.name="weight" attr.type="double"/>
+              <key id="d1" for="node" yfiles.type="nodegraphics"/>
+              <graph id="G" edgedefault="directed">
+                <node id="a"><data key="d0">1.5</data><data key="d1"><y:ShapeNode/></data></node>
+                <node id="b"/>
+                <edge id="e0" source="a" target="b"/>
+              </graph>
+            </graphml>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            graph.components(&"a".to_string()),
+            Some(&Components::from([(
+                "weight".to_string(),
+                Value::from(1.5)
+            )]))
+        );
+        assert!(graph.contains_edge(&"edge".to_string(), &"a".to_string(), &"b".to_string()));
+        assert!(TestGraph::from_graphml("<graph/>").is_err());
+    }
+}
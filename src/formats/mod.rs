@@ -0,0 +1,111 @@
+//! Exchange formats for other graph tools.
+
+#[cfg(feature = "csv")]
+mod csv;
+mod cytoscape;
+#[cfg(feature = "xml")]
+mod gexf;
+#[cfg(feature = "xml")]
+mod graphml;
+#[cfg(feature = "ron")]
+mod ron;
+#[cfg(feature = "xml")]
+mod xml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+use crate::{Components, EntityGraph};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::hash::Hash;
+
+/// How an ID, component key or relationship is written in other formats: the value itself
+/// for strings, otherwise its JSON text.
+pub(crate) fn node_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Reads back a name written by `node_name`.
+#[cfg(any(feature = "csv", feature = "xml"))]
+pub(crate) fn parse_name<T: serde::de::DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(Value::String(name.to_string()))
+        .ok()
+        .or_else(|| serde_json::from_str(name).ok())
+}
+
+/// The type for a component key's values, named as in GraphML and GEXF, and whether they need
+/// to be written as JSON text because no type fits them all.
+#[cfg(feature = "xml")]
+pub(crate) fn attribute_type<'a>(
+    mut values: impl Iterator<Item = &'a Value> + Clone,
+) -> (&'static str, bool) {
+    if values.clone().all(Value::is_string) {
+        ("string", false)
+    } else if values.clone().all(Value::is_boolean) {
+        ("boolean", false)
+    } else if values.clone().all(|value| value.is_i64() || value.is_u64()) {
+        ("long", false)
+    } else if values.all(Value::is_number) {
+        ("double", false)
+    } else {
+        ("string", true)
+    }
+}
+
+/// A component value as attribute text: strings as they are unless `json` is set, anything
+/// else as JSON.
+#[cfg(feature = "xml")]
+pub(crate) fn value_text(value: &Value, json: bool) -> String {
+    match value {
+        Value::String(text) if !json => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub(crate) type NodeRecord<'a, ID, K> = (String, &'a ID, &'a Components<K>);
+
+/// Every entity with its name from `node_name`, sorted by name.
+pub(crate) fn node_list<ID, K, R>(graph: &EntityGraph<ID, K, R>) -> Vec<NodeRecord<'_, ID, K>>
+where
+    ID: Eq + Hash + Clone + Serialize,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone,
+{
+    let mut nodes = graph
+        .entities
+        .iter()
+        .map(|(id, components)| (node_name(id), id, components))
+        .collect::<Vec<_>>();
+    nodes.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    nodes
+}
+
+pub(crate) type EdgeRecord<'a> = (String, String, String, Option<&'a Value>);
+
+/// Every edge as `(relationship, from, to, payload)` with names from `node_name`, sorted.
+pub(crate) fn edge_list<ID, K, R>(graph: &EntityGraph<ID, K, R>) -> Vec<EdgeRecord<'_>>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone + Serialize,
+{
+    let mut edges = Vec::new();
+    for (relationship, adjacency_list) in &graph.relationships {
+        for (from, neighbors) in &adjacency_list.edges {
+            for to in neighbors {
+                edges.push((
+                    node_name(relationship),
+                    node_name(from),
+                    node_name(to),
+                    adjacency_list.edge_data(from, to),
+                ));
+            }
+        }
+    }
+    edges.sort_by(|(r1, f1, t1, _), (r2, f2, t2, _)| (r1, f1, t1).cmp(&(r2, f2, t2)));
+    edges
+}
@@ -0,0 +1,103 @@
+//! The serialized graph as RON, written and read with the `ron` crate through the same serde
+//! structure as `serialize`.
+
+use crate::{EntityGraph, EntityGraphError, TypeRegistry};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, hash::Hash};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The graph as RON, with the same structure as `serialize`.
+    pub fn to_ron(&self) -> Result<String, EntityGraphError> {
+        ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(|error| EntityGraphError::SerializationError(error.to_string()))
+    }
+
+    /// Reads a graph written by `to_ron` or by hand, checking its components against
+    /// `registry` like `deserialize_with_registry`.
+    pub fn from_ron(text: &str, registry: &TypeRegistry) -> Result<Self, EntityGraphError> {
+        let graph: Self = ron::from_str(text).map_err(|error| {
+            EntityGraphError::DeserializationError(format!("Invalid RON graph: {}", error))
+        })?;
+        graph
+            .apply_registry(registry, registry.unknown_component_policy())
+            .map(|(graph, _)| graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::{json, Value};
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn ron_round_trip() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(-12)),
+                    ("name".to_string(), Value::from("Main \"backup\"\n")),
+                    (
+                        "spec".to_string(),
+                        json!({ "ports": [1, 2.5], "spare": null }),
+                    ),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity("led".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                json!({ "amps": 0.5 }),
+            )
+            .unwrap();
+        graph.add_label(&"led".to_string(), "output").unwrap();
+
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("volts");
+        registry.register::<String>("name");
+        registry.register::<Value>("spec");
+        let ron = graph.to_ron().unwrap();
+        assert_eq!(TestGraph::from_ron(&ron, &registry).unwrap(), graph);
+        assert!(TestGraph::from_ron(&ron, &TypeRegistry::new()).is_err());
+
+        let fixture = r##"
+            // Hand-written
+            (
+                entities: {
+                    "led": {},
+                    "psu": { "volts": -12, "name": r#"Main "backup""#, "spec": { "ports": [1, 2.5], "spare": None } },
+                },
+                relationships: {
+                    "powers": (edges: { "psu": ["led"] }, data: { "psu": { "led": { "amps": 0.5 } } }),
+                },
+                labels: { "led": ["output"] },
+            )
+        "##;
+        let fixture = TestGraph::from_ron(fixture, &registry).unwrap();
+        assert_eq!(
+            fixture.get_component(&"psu".to_string(), &"name".to_string()),
+            Some(&Value::from("Main \"backup\""))
+        );
+        assert!(fixture.contains_edge(
+            &"powers".to_string(),
+            &"psu".to_string(),
+            &"led".to_string()
+        ));
+        assert!(TestGraph::from_ron("(entities: {", &registry).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Reading and writing the XML interchange formats with `quick-xml`. Documents are read into a
+//! tree of elements with their attributes and text; comments, processing instructions and
+//! doctypes are skipped.
+
+use quick_xml::{
+    events::{BytesDecl, BytesStart, Event},
+    Reader, Writer,
+};
+use std::io;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<Element>,
+    pub(crate) text: String,
+}
+
+impl Element {
+    fn new(start: &BytesStart) -> Result<Self, String> {
+        let attributes = start
+            .attributes()
+            .map(|attribute| {
+                let attribute = attribute.map_err(|e| e.to_string())?;
+                let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+                Ok((
+                    String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                    value.into_owned(),
+                ))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    /// The element's name without any namespace prefix.
+    pub(crate) fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn children_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children
+            .iter()
+            .filter(move |child| child.local_name() == name)
+    }
+
+    pub(crate) fn child<'a>(&'a self, name: &'a str) -> Option<&'a Element> {
+        self.children_named(name).next()
+    }
+}
+
+/// Parses a document into its root element.
+pub(crate) fn parse(input: &str) -> Result<Element, String> {
+    let mut reader = Reader::from_str(input);
+    let mut open = Vec::<Element>::new();
+    loop {
+        let element = match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(start) => {
+                open.push(Element::new(&start)?);
+                continue;
+            }
+            Event::Empty(start) => Element::new(&start)?,
+            Event::End(_) => open
+                .pop()
+                .ok_or_else(|| "Closing tag without an open element".to_string())?,
+            Event::Text(text) => {
+                if let Some(element) = open.last_mut() {
+                    element
+                        .text
+                        .push_str(&text.unescape().map_err(|e| e.to_string())?);
+                }
+                continue;
+            }
+            Event::CData(cdata) => {
+                if let Some(element) = open.last_mut() {
+                    element
+                        .text
+                        .push_str(&String::from_utf8_lossy(&cdata.into_inner()));
+                }
+                continue;
+            }
+            Event::Eof => {
+                return Err(match open.last() {
+                    Some(element) => format!("Unclosed element '{}'", element.name),
+                    None => "No root element".to_string(),
+                })
+            }
+            _ => continue,
+        };
+        match open.last_mut() {
+            Some(parent) => parent.children.push(element),
+            None => return Ok(element),
+        }
+    }
+}
+
+/// An indented document with an XML declaration, its content written by `write`.
+pub(crate) fn document(write: impl FnOnce(&mut Writer<Vec<u8>>) -> io::Result<()>) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer
+        .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+        .and_then(|()| write(&mut writer))
+        .expect("writing to memory can't fail");
+    let mut out = String::from_utf8(writer.into_inner()).expect("the document is UTF-8");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::events::BytesText;
+
+    #[test]
+    fn parse_elements() {
+        let root = parse(
+            "<?xml version=\"1.0\"?>\n<!-- comment --><a x=\"1 &amp; 2\" y='3'><b/><c>&lt;t&#x41;&gt;<![CDATA[<raw>]]></c></a>",
+        )
+        .unwrap();
+        assert_eq!(root.attribute("x"), Some("1 & 2"));
+        assert_eq!(root.attribute("y"), Some("3"));
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.child("c").unwrap().text, "<tA><raw>");
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a><b/>").is_err());
+
+        let out = document(|writer| {
+            writer
+                .create_element("a")
+                .with_attribute(("x", "\"&\""))
+                .write_text_content(BytesText::new("<t>"))?;
+            Ok(())
+        });
+        assert_eq!(
+            out,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a x=\"&quot;&amp;&quot;\">&lt;t&gt;</a>\n"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! The serialized graph as YAML, written and read with `serde_yaml` through the same serde
+//! structure as `serialize`.
+
+use crate::{EntityGraph, EntityGraphError, TypeRegistry};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, hash::Hash};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The graph as a YAML document, with the same structure as `serialize`.
+    pub fn to_yaml(&self) -> Result<String, EntityGraphError> {
+        serde_yaml::to_string(self)
+            .map_err(|error| EntityGraphError::SerializationError(error.to_string()))
+    }
+
+    /// Reads a graph written by `to_yaml` or by hand, checking its components against
+    /// `registry` like `deserialize_with_registry`.
+    pub fn from_yaml(text: &str, registry: &TypeRegistry) -> Result<Self, EntityGraphError> {
+        let graph: Self = serde_yaml::from_str(text).map_err(|error| {
+            EntityGraphError::DeserializationError(format!("Invalid YAML graph: {}", error))
+        })?;
+        graph
+            .apply_registry(registry, registry.unknown_component_policy())
+            .map(|(graph, _)| graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::{json, Value};
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn yaml_round_trip() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(-12)),
+                    ("name".to_string(), Value::from("yes: # \"backup\"")),
+                    ("model".to_string(), Value::from("PSU 12")),
+                    (
+                        "spec".to_string(),
+                        json!({ "ports": [1, 2.5, "3", [true], { "a": null }], "spare": {} }),
+                    ),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity("led".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge_with_data(
+                "powers".to_string(),
+                "psu".to_string(),
+                "led".to_string(),
+                json!({ "amps": 0.5 }),
+            )
+            .unwrap();
+        graph.add_label(&"led".to_string(), "output").unwrap();
+
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("volts");
+        registry.register::<String>("name");
+        registry.register::<String>("model");
+        registry.register::<Value>("spec");
+        let yaml = graph.to_yaml().unwrap();
+        assert_eq!(TestGraph::from_yaml(&yaml, &registry).unwrap(), graph);
+        assert!(TestGraph::from_yaml(&yaml, &TypeRegistry::new()).is_err());
+
+        let fixture = "
+# Hand-written
+entities:
+  led: {}
+  psu:
+    volts: -12
+    name: 'It''s main'  # the main supply
+    spec: { ports: [1, 2.5], spare: ~ }
+relationships:
+  powers:
+    edges:
+      psu:
+      - led
+    data: { psu: { led: { amps: 0.5 } } }
+labels:
+  led: [output]
+";
+        let fixture = TestGraph::from_yaml(fixture, &registry).unwrap();
+        assert_eq!(
+            fixture.get_component(&"psu".to_string(), &"name".to_string()),
+            Some(&Value::from("It's main"))
+        );
+        assert!(fixture.contains_edge(
+            &"powers".to_string(),
+            &"psu".to_string(),
+            &"led".to_string()
+        ));
+        assert!(TestGraph::from_yaml("entities:\n  led: {}\n   psu: {}\n", &registry).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::{
+    changes::ChangeTracker,
+    graph::{reverse_relationships, Map},
+    AdjacencyList, Components, DuplicateEdgePolicy, EntityGraph, LabelSet, Metadata,
+};
+use boomphf::Mphf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// Compressed sparse row adjacency for one relationship: the neighbors of the entity at dense
+/// index `i` are `targets[offsets[i]..offsets[i + 1]]`, and `data[j]` is the payload of the
+/// edge to `targets[j]`.
+#[derive(Debug, Clone)]
+struct Csr {
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+    data: Vec<Option<Value>>,
+}
+
+impl Csr {
+    fn neighbors(&self, index: usize) -> &[u32] {
+        &self.targets[self.offsets[index]..self.offsets[index + 1]]
+    }
+
+    fn edge_data(&self, from: usize, to: u32) -> Option<&Value> {
+        let start = self.offsets[from];
+        let position = self
+            .neighbors(from)
+            .iter()
+            .position(|target| *target == to)?;
+        self.data[start + position].as_ref()
+    }
+}
+
+/// Places each of `keys` at the position a minimal perfect hash built over them gives it.
+/// `keys` must not repeat.
+fn perfect_hash<T: Hash + Debug>(keys: Vec<T>) -> (Mphf<T>, Vec<T>) {
+    let hash = Mphf::new(1.7, &keys);
+    let mut slots = keys.into_iter().map(Some).collect::<Vec<_>>();
+    let mut position = 0;
+    while position < slots.len() {
+        let target = hash.hash(slots[position].as_ref().unwrap()) as usize;
+        if target == position {
+            position += 1;
+        } else {
+            slots.swap(position, target);
+        }
+    }
+    (hash, slots.into_iter().map(Option::unwrap).collect())
+}
+
+/// An immutable snapshot of an `EntityGraph` laid out for fast reads. Entities are stored
+/// densely at the position a minimal perfect hash of their ID gives them, and each
+/// relationship's edges are packed into CSR arrays, so neighbor queries are one hash followed
+/// by a slice.
+#[derive(Debug, Clone)]
+pub struct FrozenGraph<ID, K, R> {
+    ids: Vec<ID>,
+    index: Mphf<ID>,
+    components: Vec<Components<K>>,
+    labels: Vec<LabelSet>,
+    relationships: Vec<R>,
+    relationship_index: Mphf<R>,
+    adjacency: Vec<Csr>,
+    edge_policies: Map<R, DuplicateEdgePolicy>,
+    metadata: Option<Metadata>,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Debug + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Debug + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Builds a read-optimized copy of the graph. Edges that point at IDs without an entity
+    /// are dropped.
+    pub fn freeze(&self) -> FrozenGraph<ID, K, R> {
+        let (hash, ids) = perfect_hash(self.entities.keys().cloned().collect());
+        let index = |id: &ID| hash.try_hash(id).map(|position| position as u32);
+        let components = ids.iter().map(|id| self.entities[id].clone()).collect();
+        let labels = ids
+            .iter()
+            .map(|id| self.labels.get(id).cloned().unwrap_or_default())
+            .collect();
+
+        let (relationship_index, relationships) =
+            perfect_hash(self.relationships.keys().cloned().collect());
+        let mut adjacency = Vec::with_capacity(relationships.len());
+        for relationship in &relationships {
+            let adjacency_list = &self.relationships[relationship];
+            let mut offsets = Vec::with_capacity(ids.len() + 1);
+            let mut targets = Vec::new();
+            let mut data = Vec::new();
+            offsets.push(0);
+            for id in &ids {
+                for to in adjacency_list.edges.get(id).into_iter().flatten() {
+                    if let Some(target) = index(to) {
+                        targets.push(target);
+                        data.push(adjacency_list.edge_data(id, to).cloned());
+                    }
+                }
+                offsets.push(targets.len());
+            }
+            adjacency.push(Csr {
+                offsets,
+                targets,
+                data,
+            });
+        }
+
+        FrozenGraph {
+            ids,
+            index: hash,
+            components,
+            labels,
+            relationships,
+            relationship_index,
+            adjacency,
+            edge_policies: self.edge_policies.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl<ID, K, R> FrozenGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Debug,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone + Debug,
+{
+    /// The dense position of `id`. The hash gives every other ID some position too, so the ID
+    /// stored there is checked.
+    fn position(&self, id: &ID) -> Option<usize> {
+        let position = self.index.try_hash(id)? as usize;
+        (self.ids.get(position)? == id).then_some(position)
+    }
+
+    fn relationship_position(&self, relationship: &R) -> Option<usize> {
+        let position = self.relationship_index.try_hash(relationship)? as usize;
+        (self.relationships.get(position)? == relationship).then_some(position)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.iter().map(|csr| csr.targets.len()).sum()
+    }
+
+    pub fn contains(&self, id: &ID) -> bool {
+        self.position(id).is_some()
+    }
+
+    pub fn components(&self, id: &ID) -> Option<&Components<K>> {
+        Some(&self.components[self.position(id)?])
+    }
+
+    pub fn get_component(&self, id: &ID, component_key: &K) -> Option<&Value> {
+        self.components(id)?.get(component_key)
+    }
+
+    pub fn labels(&self, id: &ID) -> Option<&LabelSet> {
+        Some(&self.labels[self.position(id)?])
+    }
+
+    /// Neighbors of `id` over `relationship`, in edge insertion order.
+    pub fn neighbors<'a>(&'a self, id: &ID, relationship: &R) -> impl Iterator<Item = &'a ID> + 'a {
+        let neighbors = match (self.position(id), self.relationship_position(relationship)) {
+            (Some(index), Some(relationship)) => self.adjacency[relationship].neighbors(index),
+            _ => &[],
+        };
+        neighbors.iter().map(|target| &self.ids[*target as usize])
+    }
+
+    /// The payload of the `from -> to` edge of `relationship`, if it has one.
+    pub fn edge_data(&self, relationship: &R, from: &ID, to: &ID) -> Option<&Value> {
+        let relationship = self.relationship_position(relationship)?;
+        let to = self.position(to)? as u32;
+        self.adjacency[relationship].edge_data(self.position(from)?, to)
+    }
+
+    /// Neighbors of `id` over every relationship.
+    pub fn all_neighbors<'a>(&'a self, id: &ID) -> impl Iterator<Item = (&'a R, &'a ID)> + 'a {
+        let index = self.position(id);
+        self.relationships
+            .iter()
+            .zip(&self.adjacency)
+            .flat_map(move |(relationship, csr)| {
+                index
+                    .map(|index| csr.neighbors(index))
+                    .unwrap_or(&[])
+                    .iter()
+                    .map(move |target| (relationship, &self.ids[*target as usize]))
+            })
+    }
+
+    /// Converts back into a mutable `EntityGraph`.
+    pub fn thaw(self) -> EntityGraph<ID, K, R> {
+        let mut graph = EntityGraph {
+            edge_policies: self.edge_policies,
+            metadata: self.metadata,
+            ..EntityGraph::default()
+        };
+        for (relationship, csr) in self.relationships.into_iter().zip(&self.adjacency) {
+            let mut adjacency_list = AdjacencyList::new();
+            for (index, id) in self.ids.iter().enumerate() {
+                let neighbors = csr.neighbors(index);
+                if !neighbors.is_empty() {
+                    adjacency_list.edges.insert(
+                        id.clone(),
+                        neighbors
+                            .iter()
+                            .map(|target| self.ids[*target as usize].clone())
+                            .collect(),
+                    );
+                }
+                let start = csr.offsets[index];
+                for (offset, target) in neighbors.iter().enumerate() {
+                    if let Some(payload) = &csr.data[start + offset] {
+                        adjacency_list.set_edge_data(
+                            id.clone(),
+                            self.ids[*target as usize].clone(),
+                            payload.clone(),
+                        );
+                    }
+                }
+            }
+            graph.relationships.insert(relationship, adjacency_list);
+        }
+        for ((id, components), labels) in self.ids.into_iter().zip(self.components).zip(self.labels)
+        {
+            for label in &labels {
+                graph
+                    .label_index
+                    .entry(label.clone())
+                    .or_default()
+                    .insert(id.clone());
+            }
+            if !labels.is_empty() {
+                graph.labels.insert(id.clone(), labels);
+            }
+            graph.entities.insert(id, components);
+        }
+        graph.incoming = reverse_relationships(&graph.relationships);
+        graph.changes = ChangeTracker::with_entities(graph.entities.keys());
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn sample() -> TestGraph {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C"] {
+            graph
+                .add_entity(
+                    id.to_string(),
+                    vec![("name".to_string(), Value::from(id))]
+                        .into_iter()
+                        .collect(),
+                )
+                .unwrap();
+        }
+        graph
+            .add_edge("child".to_string(), "A".to_string(), "B".to_string())
+            .unwrap();
+        graph
+            .add_edge("child".to_string(), "A".to_string(), "C".to_string())
+            .unwrap();
+        graph
+            .add_edge("power".to_string(), "C".to_string(), "A".to_string())
+            .unwrap();
+        graph.add_label(&"B".to_string(), "leaf").unwrap();
+        graph
+    }
+
+    #[test]
+    fn frozen_queries() {
+        let frozen = sample().freeze();
+        assert_eq!(frozen.node_count(), 3);
+        assert_eq!(frozen.edge_count(), 3);
+        assert_eq!(
+            frozen
+                .neighbors(&"A".to_string(), &"child".to_string())
+                .collect::<Vec<_>>(),
+            vec!["B", "C"]
+        );
+        assert_eq!(
+            frozen
+                .neighbors(&"A".to_string(), &"power".to_string())
+                .count(),
+            0
+        );
+        assert_eq!(
+            frozen.all_neighbors(&"C".to_string()).collect::<Vec<_>>(),
+            vec![(&"power".to_string(), &"A".to_string())]
+        );
+        assert_eq!(
+            frozen.get_component(&"B".to_string(), &"name".to_string()),
+            Some(&Value::from("B"))
+        );
+        assert!(frozen.labels(&"B".to_string()).unwrap().contains("leaf"));
+        assert!(!frozen.contains(&"D".to_string()));
+    }
+
+    #[test]
+    fn thaw_restores_graph() {
+        let mut graph = sample();
+        graph
+            .set_edge_data(
+                &"child".to_string(),
+                &"A".to_string(),
+                &"C".to_string(),
+                Value::from(2),
+            )
+            .unwrap();
+        graph.set_duplicate_edge_policy("child".to_string(), DuplicateEdgePolicy::DedupSilently);
+        graph.set_metadata(Metadata::default().title("Plant floor"));
+
+        let frozen = graph.freeze();
+        assert_eq!(
+            frozen.edge_data(&"child".to_string(), &"A".to_string(), &"C".to_string()),
+            Some(&Value::from(2))
+        );
+        assert_eq!(
+            frozen.edge_data(&"child".to_string(), &"A".to_string(), &"B".to_string()),
+            None
+        );
+        let thawed = frozen.thaw();
+        assert_eq!(thawed, graph);
+        assert_eq!(thawed.entities_with_label("leaf").count(), 1);
+        assert_eq!(
+            thawed.get_edge_data(&"child".to_string(), &"A".to_string(), &"C".to_string()),
+            Some(&Value::from(2))
+        );
+        assert_eq!(
+            thawed.duplicate_edge_policy(&"child".to_string()),
+            DuplicateEdgePolicy::DedupSilently
+        );
+        assert_eq!(
+            thawed.metadata().unwrap().title.as_deref(),
+            Some("Plant floor")
+        );
+    }
+
+    #[test]
+    fn frozen_lookups_reject_unknown_keys() {
+        let frozen = TestGraph::new().freeze();
+        assert!(!frozen.contains(&"A".to_string()));
+        let frozen = sample().freeze();
+        for id in ["D", "", "AA", "leaf"] {
+            assert!(!frozen.contains(&id.to_string()));
+            assert!(frozen.components(&id.to_string()).is_none());
+        }
+        assert_eq!(
+            frozen
+                .neighbors(&"A".to_string(), &"missing".to_string())
+                .count(),
+            0
+        );
+    }
+}
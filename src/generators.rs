@@ -0,0 +1,223 @@
+//! Deterministic generators for common graph shapes. The same seed and parameters always
+//! produce the same graph, so generated graphs can stand in for fixtures in tests and
+//! benchmarks.
+
+use crate::{Components, EntityGraph};
+
+/// The graph type produced by a `Generator`. Entities are numbered from zero.
+pub type GeneratedGraph = EntityGraph<usize, String, String>;
+
+type ComponentStub = Box<dyn FnMut(usize, &mut SplitMix64) -> Components<String>>;
+
+/// A small, fast, seedable PRNG. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniformly distributed integer in `[0, bound)`. `bound` must be non-zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Builds graphs of a given shape. Every edge uses the configured relationship (`"edge"` by
+/// default) and every entity gets the components returned by the component stub (none by
+/// default).
+pub struct Generator {
+    rng: SplitMix64,
+    relationship: String,
+    components: ComponentStub,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+            relationship: "edge".to_string(),
+            components: Box::new(|_, _| Components::new()),
+        }
+    }
+
+    pub fn relationship(mut self, relationship: impl Into<String>) -> Self {
+        self.relationship = relationship.into();
+        self
+    }
+
+    /// Sets the function that produces each entity's components from its ID.
+    pub fn components<F>(mut self, components: F) -> Self
+    where
+        F: FnMut(usize, &mut SplitMix64) -> Components<String> + 'static,
+    {
+        self.components = Box::new(components);
+        self
+    }
+
+    fn entities(&mut self, count: usize) -> GeneratedGraph {
+        let mut graph = GeneratedGraph::new();
+        for id in 0..count {
+            let components = (self.components)(id, &mut self.rng);
+            graph.add_entity(id, components).unwrap();
+        }
+        graph
+    }
+
+    fn connect(&self, graph: &mut GeneratedGraph, from: usize, to: usize) {
+        graph.add_edge(self.relationship.clone(), from, to).unwrap();
+    }
+
+    /// G(n, p): each pair `i < j` gets an edge `i -> j` with the given probability.
+    pub fn erdos_renyi(&mut self, nodes: usize, probability: f64) -> GeneratedGraph {
+        let mut graph = self.entities(nodes);
+        for from in 0..nodes {
+            for to in from + 1..nodes {
+                if self.rng.next_f64() < probability {
+                    self.connect(&mut graph, from, to);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Preferential attachment: each new entity links to `edges_per_node` distinct earlier
+    /// entities, chosen with probability proportional to their degree.
+    pub fn barabasi_albert(&mut self, nodes: usize, edges_per_node: usize) -> GeneratedGraph {
+        let mut graph = self.entities(nodes);
+        let seed_nodes = edges_per_node.max(1).min(nodes);
+        // Every edge endpoint appears once here, so a uniform pick is a degree-weighted pick.
+        let mut endpoints = (0..seed_nodes).collect::<Vec<_>>();
+        for from in seed_nodes..nodes {
+            let mut targets = Vec::new();
+            while targets.len() < edges_per_node.min(from) {
+                let to = endpoints[self.rng.below(endpoints.len())];
+                if !targets.contains(&to) {
+                    targets.push(to);
+                }
+            }
+            for to in targets {
+                self.connect(&mut graph, from, to);
+                endpoints.push(from);
+                endpoints.push(to);
+            }
+        }
+        graph
+    }
+
+    /// A complete `branching`-ary tree with `nodes` entities, rooted at 0, with edges from
+    /// parent to child.
+    pub fn tree(&mut self, nodes: usize, branching: usize) -> GeneratedGraph {
+        let mut graph = self.entities(nodes);
+        let branching = branching.max(1);
+        for child in 1..nodes {
+            self.connect(&mut graph, (child - 1) / branching, child);
+        }
+        graph
+    }
+
+    /// A uniformly shaped random tree rooted at 0: each entity's parent is a random earlier
+    /// entity.
+    pub fn random_tree(&mut self, nodes: usize) -> GeneratedGraph {
+        let mut graph = self.entities(nodes);
+        for child in 1..nodes {
+            let parent = self.rng.below(child);
+            self.connect(&mut graph, parent, child);
+        }
+        graph
+    }
+
+    /// A `width` by `height` lattice. Entity `y * width + x` links right and down.
+    pub fn grid(&mut self, width: usize, height: usize) -> GeneratedGraph {
+        let mut graph = self.entities(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let id = y * width + x;
+                if x + 1 < width {
+                    self.connect(&mut graph, id, id + 1);
+                }
+                if y + 1 < height {
+                    self.connect(&mut graph, id, id + width);
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn edge_count(graph: &GeneratedGraph) -> usize {
+        graph
+            .relationships
+            .values()
+            .flat_map(|adjacency_list| adjacency_list.edges.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    #[test]
+    fn generators_are_deterministic() {
+        let first = Generator::new(7).erdos_renyi(30, 0.2);
+        let second = Generator::new(7).erdos_renyi(30, 0.2);
+        assert_eq!(first, second);
+        assert_ne!(first, Generator::new(8).erdos_renyi(30, 0.2));
+
+        let first = Generator::new(7).barabasi_albert(50, 2);
+        assert_eq!(first, Generator::new(7).barabasi_albert(50, 2));
+        assert_eq!(edge_count(&first), (50 - 2) * 2);
+    }
+
+    #[test]
+    fn generated_shapes() {
+        let tree = Generator::new(0).tree(7, 2);
+        assert_eq!(edge_count(&tree), 6);
+        assert_eq!(tree.traverse_bfs(0).unwrap().len(), 7);
+
+        let random_tree = Generator::new(3).random_tree(20);
+        assert_eq!(edge_count(&random_tree), 19);
+
+        let grid = Generator::new(0).relationship("adjacent").grid(3, 2);
+        assert_eq!(grid.entities.len(), 6);
+        assert_eq!(edge_count(&grid), 7);
+        assert!(grid.relationships.contains_key("adjacent"));
+    }
+
+    #[test]
+    fn component_stubs() {
+        let graph = Generator::new(1)
+            .components(|id, rng| {
+                vec![
+                    ("index".to_string(), Value::from(id)),
+                    ("weight".to_string(), Value::from(rng.below(10))),
+                ]
+                .into_iter()
+                .collect()
+            })
+            .grid(2, 2);
+        assert_eq!(
+            graph.get_component(&3, &"index".to_string()),
+            Some(&Value::from(3))
+        );
+        assert!(graph.get_component(&0, &"weight".to_string()).is_some());
+    }
+}
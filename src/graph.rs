@@ -1,37 +1,220 @@
+use crate::{
+    changes::ChangeTracker, envelope, DuplicateEdgePolicy, Metadata, TraversalOptions,
+    FORMAT_VERSION,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::{
     any::Any,
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     error::Error,
-    fmt::Display,
+    fmt::{Debug, Display},
     hash::Hash,
+    io::{BufReader, BufWriter, Read, Write},
 };
 
+/// Entity IDs in errors are their JSON form, e.g. `"door"` for a `String` ID, so that errors
+/// can be reported for any ID type. `ID` is only used by errors that return entity IDs, such as
+/// `CycleDetected`.
 #[derive(Debug)]
-pub enum EntityGraphError {
-    EntityAlreadyExists,
-    EntityNotFound,
-    EdgeError,
+pub enum EntityGraphError<ID = ()> {
+    EntityAlreadyExists {
+        id: String,
+    },
+    EntityNotFound {
+        id: String,
+    },
+    /// An edge endpoint that does not exist.
+    EdgeError {
+        relationship: String,
+        endpoint: Endpoint,
+        id: String,
+    },
+    EdgeNotFound {
+        relationship: String,
+        from: String,
+        to: String,
+    },
+    /// An edge rejected by a relationship using `DuplicateEdgePolicy::ErrorOnDuplicate`.
+    DuplicateEdge {
+        relationship: String,
+        from: String,
+        to: String,
+    },
+    RelationshipNotFound {
+        relationship: String,
+    },
+    ComponentAlreadyExists {
+        id: String,
+        component: String,
+    },
+    ComponentNotFound {
+        id: String,
+        component: String,
+    },
+    /// An archetype that hasn't been registered with the graph.
+    ArchetypeNotFound {
+        name: String,
+    },
+    /// A prefab parameter without an argument.
+    MissingArgument {
+        parameter: String,
+    },
+    /// A kind missing from the `KindRegistry` used.
+    UnknownKind {
+        kind: String,
+    },
+    /// An edge whose relationship the kind of its source doesn't allow.
+    RelationshipNotAllowed {
+        relationship: String,
+        kind: String,
+        id: String,
+    },
     SerializationError(String),
     DeserializationError(String),
+    /// Serialized data that doesn't match the checksum saved with it.
+    IntegrityError(String),
+    /// The entities along a cycle, starting and ending with the same entity.
+    CycleDetected(Vec<ID>),
+    /// An error annotated with the operation that failed, see `EntityGraphError::context`.
+    Context {
+        context: String,
+        source: Box<EntityGraphError<ID>>,
+    },
+}
+
+/// Which end of an edge an error refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Source,
+    Target,
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Endpoint::Source => write!(f, "source"),
+            Endpoint::Target => write!(f, "target"),
+        }
+    }
+}
+
+/// The JSON form of an entity ID, as used in error messages.
+pub(crate) fn id_string<ID: Serialize>(id: &ID) -> String {
+    serde_json::to_string(id).unwrap_or_else(|_| "<unserializable id>".to_string())
+}
+
+impl<ID> EntityGraphError<ID> {
+    /// Wraps the error with a description of what was being done when it happened.
+    pub fn context(self, context: impl Into<String>) -> Self {
+        EntityGraphError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The error without any context added by `context`.
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            EntityGraphError::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+
+    pub(crate) fn entity_already_exists(id: &impl Serialize) -> Self {
+        EntityGraphError::EntityAlreadyExists { id: id_string(id) }
+    }
+
+    pub(crate) fn entity_not_found(id: &impl Serialize) -> Self {
+        EntityGraphError::EntityNotFound { id: id_string(id) }
+    }
+
+    pub(crate) fn relationship_not_found(relationship: &impl Display) -> Self {
+        EntityGraphError::RelationshipNotFound {
+            relationship: relationship.to_string(),
+        }
+    }
+
+    pub(crate) fn component_not_found(id: &impl Serialize, component: &impl Display) -> Self {
+        EntityGraphError::ComponentNotFound {
+            id: id_string(id),
+            component: component.to_string(),
+        }
+    }
 }
 
-impl Display for EntityGraphError {
+impl<ID: Debug> Display for EntityGraphError<ID> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            EntityGraphError::EntityAlreadyExists => {
-                write!(f, "Entity with this ID already exists")
+            EntityGraphError::EntityAlreadyExists { id } => {
+                write!(f, "Entity {} already exists", id)
+            }
+            EntityGraphError::EntityNotFound { id } => write!(f, "Entity {} does not exist", id),
+            EntityGraphError::EdgeError {
+                relationship,
+                endpoint,
+                id,
+            } => write!(
+                f,
+                "Cannot add '{}' edge: {} entity {} does not exist",
+                relationship, endpoint, id
+            ),
+            EntityGraphError::EdgeNotFound {
+                relationship,
+                from,
+                to,
+            } => write!(f, "No '{}' edge from {} to {}", relationship, from, to),
+            EntityGraphError::DuplicateEdge {
+                relationship,
+                from,
+                to,
+            } => write!(
+                f,
+                "Duplicate '{}' edge from {} to {}",
+                relationship, from, to
+            ),
+            EntityGraphError::RelationshipNotFound { relationship } => {
+                write!(f, "Relationship '{}' does not exist", relationship)
+            }
+            EntityGraphError::ComponentAlreadyExists { id, component } => {
+                write!(f, "Entity {} already has a '{}' component", id, component)
+            }
+            EntityGraphError::ComponentNotFound { id, component } => {
+                write!(f, "Entity {} has no '{}' component", id, component)
             }
-            EntityGraphError::EntityNotFound => write!(f, "Entity with this ID does not exist"),
-            EntityGraphError::EdgeError => write!(f, "One of the entity IDs does not exist"),
+            EntityGraphError::ArchetypeNotFound { name } => {
+                write!(f, "Archetype '{}' not found", name)
+            }
+            EntityGraphError::MissingArgument { parameter } => {
+                write!(f, "No argument for prefab parameter '{}'", parameter)
+            }
+            EntityGraphError::UnknownKind { kind } => write!(f, "Unknown entity kind '{}'", kind),
+            EntityGraphError::RelationshipNotAllowed {
+                relationship,
+                kind,
+                id,
+            } => write!(
+                f,
+                "Entity {} of kind '{}' cannot have '{}' edges",
+                id, kind, relationship
+            ),
             EntityGraphError::SerializationError(e) => write!(f, "Serialization error: {}", e),
             EntityGraphError::DeserializationError(e) => write!(f, "Deserialization error: {}", e),
+            EntityGraphError::IntegrityError(e) => write!(f, "Integrity error: {}", e),
+            EntityGraphError::CycleDetected(cycle) => write!(f, "Cycle detected: {:?}", cycle),
+            EntityGraphError::Context { context, source } => write!(f, "{}: {}", context, source),
         }
     }
 }
 
-impl Error for EntityGraphError {}
+impl<ID: Debug + 'static> Error for EntityGraphError<ID> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EntityGraphError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 pub trait EntityId: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> {}
 impl<T> EntityId for T where T: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> {}
@@ -39,18 +222,345 @@ impl<T> EntityId for T where T: Eq + Hash + Clone + Serialize + for<'de> Deseria
 pub trait MapKey: Eq + Hash + Clone {}
 impl<T> MapKey for T where T: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> {}
 
-type Entities<ID, K> = HashMap<ID, HashMap<K, Value>>;
-type Relationships<ID, R> = HashMap<R, AdjacencyList<ID>>;
+/// The map behind entities, labels and edges. With the `indexmap` feature it keeps insertion
+/// order, which makes iteration, traversal and serialization order reproducible.
+#[cfg(feature = "indexmap")]
+pub(crate) type Map<K, V> = indexmap::IndexMap<K, V>;
+#[cfg(not(feature = "indexmap"))]
+pub(crate) type Map<K, V> = HashMap<K, V>;
+
+/// Lets removals use `IndexMap::shift_remove`, which keeps the remaining entries in order,
+/// whichever map is in use.
+#[cfg(not(feature = "indexmap"))]
+pub(crate) trait ShiftRemove<K, V> {
+    fn shift_remove(&mut self, key: &K) -> Option<V>;
+}
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg(not(feature = "indexmap"))]
+impl<K: Eq + Hash, V> ShiftRemove<K, V> for HashMap<K, V> {
+    fn shift_remove(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+}
+
+/// `ShiftRemove` for label sets, which are looked up by `&str`.
+#[cfg(not(feature = "indexmap"))]
+pub(crate) trait ShiftRemoveLabel {
+    fn shift_remove(&mut self, label: &str) -> bool;
+}
+
+#[cfg(not(feature = "indexmap"))]
+impl ShiftRemoveLabel for HashSet<String> {
+    fn shift_remove(&mut self, label: &str) -> bool {
+        self.remove(label)
+    }
+}
+
+/// An entity's components. With the `indexmap` feature they keep insertion order, so an
+/// entity's components serialize in the same order every time.
+#[cfg(feature = "indexmap")]
+pub type Components<K> = indexmap::IndexMap<K, Value>;
+#[cfg(not(feature = "indexmap"))]
+pub type Components<K> = HashMap<K, Value>;
+
+/// An entity's labels, kept in insertion order with the `indexmap` feature.
+#[cfg(feature = "indexmap")]
+pub type LabelSet = indexmap::IndexSet<String>;
+#[cfg(not(feature = "indexmap"))]
+pub type LabelSet = HashSet<String>;
+
+type Entities<ID, K> = Map<ID, Components<K>>;
+pub(crate) type Relationships<ID, R> = Map<R, AdjacencyList<ID>>;
+type Labels<ID> = Map<ID, LabelSet>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(
+    from = "EntityGraphData<ID, K, R>",
+    bound(deserialize = "EntityGraphData<ID, K, R>: Deserialize<'de>")
+)]
 pub struct EntityGraph<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    pub(crate) entities: Entities<ID, K>,
+    pub(crate) relationships: Relationships<ID, R>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub(crate) labels: Labels<ID>,
+    /// Policies for relationships that don't allow parallel edges.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub(crate) edge_policies: Map<R, DuplicateEdgePolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) metadata: Option<Metadata>,
+    // Derived from `labels` and rebuilt on deserialization.
+    #[serde(skip)]
+    pub(crate) label_index: HashMap<String, HashSet<ID>>,
+    // Every edge keyed by its target, derived from `relationships`.
+    #[serde(skip)]
+    pub(crate) incoming: Relationships<ID, R>,
+    #[serde(skip)]
+    pub(crate) changes: ChangeTracker<ID>,
+    // Registered with `register_archetype`, not saved with the graph.
+    #[serde(skip)]
+    pub(crate) archetypes: HashMap<String, Components<K>>,
+}
+
+/// The serialized form of an `EntityGraph`, without any of its derived indexes.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "ID: Deserialize<'de>, K: Deserialize<'de>, R: Deserialize<'de>"))]
+struct EntityGraphData<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
     entities: Entities<ID, K>,
     relationships: Relationships<ID, R>,
+    #[serde(default)]
+    labels: Labels<ID>,
+    #[serde(default = "Map::new")]
+    edge_policies: Map<R, DuplicateEdgePolicy>,
+    #[serde(default)]
+    metadata: Option<Metadata>,
+}
+
+impl<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone>
+    From<EntityGraphData<ID, K, R>> for EntityGraph<ID, K, R>
+{
+    fn from(data: EntityGraphData<ID, K, R>) -> Self {
+        let mut label_index: HashMap<String, HashSet<ID>> = HashMap::new();
+        for (id, labels) in &data.labels {
+            for label in labels {
+                label_index
+                    .entry(label.clone())
+                    .or_default()
+                    .insert(id.clone());
+            }
+        }
+        EntityGraph {
+            changes: ChangeTracker::with_entities(data.entities.keys()),
+            archetypes: HashMap::new(),
+            incoming: reverse_relationships(&data.relationships),
+            entities: data.entities,
+            relationships: data.relationships,
+            labels: data.labels,
+            edge_policies: data.edge_policies,
+            metadata: data.metadata,
+            label_index,
+        }
+    }
+}
+
+impl<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> PartialEq
+    for EntityGraph<ID, K, R>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.entities == other.entities
+            && self.relationships == other.relationships
+            && self.labels == other.labels
+    }
+}
+
+/// Inserts or replaces entities like `upsert_entity`.
+impl<ID, K, R> Extend<(ID, Components<K>)> for EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    fn extend<T: IntoIterator<Item = (ID, Components<K>)>>(&mut self, entities: T) {
+        let entities = entities.into_iter();
+        self.entities.reserve(entities.size_hint().0);
+        for (id, components) in entities {
+            self.upsert_entity(id, components);
+        }
+    }
+}
+
+/// Builds a graph without edges. Later entities replace earlier ones with the same ID.
+impl<ID, K, R> FromIterator<(ID, Components<K>)> for EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    fn from_iter<T: IntoIterator<Item = (ID, Components<K>)>>(entities: T) -> Self {
+        let mut graph = EntityGraph::new();
+        graph.extend(entities);
+        graph
+    }
+}
+
+/// Depth-first iterator returned by `EntityGraph::iter_dfs`. Entities are yielded as they are
+/// first visited, so stopping early skips the rest of the traversal.
+pub struct Dfs<'a, ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    graph: &'a EntityGraph<ID, K, R>,
+    visited: HashSet<ID>,
+    stack: Vec<ID>,
+}
+
+impl<ID, K, R> Iterator for Dfs<'_, ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    type Item = ID;
+
+    fn next(&mut self) -> Option<ID> {
+        while let Some(current) = self.stack.pop() {
+            if !self.visited.insert(current.clone()) {
+                continue;
+            }
+            if let Some(neighbors) = self.graph.get_neighbors(&current) {
+                for neighbor in neighbors {
+                    if !self.visited.contains(neighbor) {
+                        self.stack.push(neighbor.clone());
+                    }
+                }
+            }
+            return Some(current);
+        }
+        None
+    }
+}
+
+/// Breadth-first iterator returned by `EntityGraph::iter_bfs`.
+pub struct Bfs<'a, ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    graph: &'a EntityGraph<ID, K, R>,
+    visited: HashSet<ID>,
+    queue: VecDeque<ID>,
+}
+
+impl<ID, K, R> Iterator for Bfs<'_, ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    type Item = ID;
+
+    fn next(&mut self) -> Option<ID> {
+        let current = self.queue.pop_front()?;
+        if let Some(neighbors) = self.graph.get_neighbors(&current) {
+            for neighbor in neighbors {
+                if self.visited.insert(neighbor.clone()) {
+                    self.queue.push_back(neighbor.clone());
+                }
+            }
+        }
+        Some(current)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct AdjacencyList<ID: Eq + Hash + Clone> {
-    edges: HashMap<ID, Vec<ID>>,
+    pub(crate) edges: Map<ID, Vec<ID>>,
+    /// Payloads keyed by source then target. Parallel `from -> to` edges share one payload,
+    /// which is dropped along with the last of them.
+    #[serde(default = "Map::new", skip_serializing_if = "Map::is_empty")]
+    pub(crate) data: Map<ID, Map<ID, Value>>,
+}
+
+impl<ID: Eq + Hash + Clone> AdjacencyList<ID> {
+    pub(crate) fn new() -> Self {
+        AdjacencyList {
+            edges: Map::new(),
+            data: Map::new(),
+        }
+    }
+
+    pub(crate) fn edge_data(&self, from: &ID, to: &ID) -> Option<&Value> {
+        self.data.get(from)?.get(to)
+    }
+
+    pub(crate) fn set_edge_data(&mut self, from: ID, to: ID, data: Value) -> Option<Value> {
+        self.data.entry(from).or_default().insert(to, data)
+    }
+
+    fn remove_edge_data(&mut self, from: &ID, to: &ID) {
+        if let Some(targets) = self.data.get_mut(from) {
+            targets.shift_remove(to);
+            if targets.is_empty() {
+                self.data.shift_remove(from);
+            }
+        }
+    }
+}
+
+/// The same edges as `relationships`, keyed by target instead of source.
+pub(crate) fn reverse_relationships<ID: Eq + Hash + Clone, R: Eq + Hash + Clone>(
+    relationships: &Relationships<ID, R>,
+) -> Relationships<ID, R> {
+    let mut incoming = Map::new();
+    for (relationship, adjacency_list) in relationships {
+        for (from, neighbors) in &adjacency_list.edges {
+            for to in neighbors {
+                insert_edge(
+                    &mut incoming,
+                    relationship.clone(),
+                    to.clone(),
+                    from.clone(),
+                );
+            }
+        }
+    }
+    incoming
+}
+
+fn insert_edge<ID: Eq + Hash + Clone, R: Eq + Hash + Clone>(
+    relationships: &mut Relationships<ID, R>,
+    relationship_key: R,
+    from: ID,
+    to: ID,
+) {
+    relationships
+        .entry(relationship_key)
+        .or_insert_with(AdjacencyList::new)
+        .edges
+        .entry(from)
+        .or_default()
+        .push(to);
+}
+
+/// Removes the first `from -> to` edge, dropping lists left empty. Returns whether an edge was
+/// removed.
+fn remove_first_edge<ID: Eq + Hash + Clone, R: Eq + Hash + Clone>(
+    relationships: &mut Relationships<ID, R>,
+    relationship_key: &R,
+    from: &ID,
+    to: &ID,
+) -> bool {
+    let Some(adjacency_list) = relationships.get_mut(relationship_key) else {
+        return false;
+    };
+    let Some(neighbors) = adjacency_list.edges.get_mut(from) else {
+        return false;
+    };
+    let Some(position) = neighbors.iter().position(|neighbor| neighbor == to) else {
+        return false;
+    };
+    neighbors.remove(position);
+    let parallel = neighbors.contains(to);
+    if neighbors.is_empty() {
+        adjacency_list.edges.shift_remove(from);
+    }
+    if !parallel {
+        adjacency_list.remove_edge_data(from, to);
+    }
+    if adjacency_list.edges.is_empty() {
+        relationships.shift_remove(relationship_key);
+    }
+    true
+}
+
+impl<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> Default
+    for EntityGraph<ID, K, R>
+{
+    fn default() -> Self {
+        EntityGraph {
+            entities: Map::new(),
+            relationships: Map::new(),
+            labels: Map::new(),
+            edge_policies: Map::new(),
+            metadata: None,
+            label_index: HashMap::new(),
+            incoming: Map::new(),
+            changes: ChangeTracker::default(),
+            archetypes: HashMap::new(),
+        }
+    }
 }
 
 impl<ID, K, R> EntityGraph<ID, K, R>
@@ -60,34 +570,106 @@ where
     R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
 {
     pub fn new() -> Self {
-        EntityGraph {
-            entities: HashMap::new(),
-            relationships: HashMap::new(),
+        Self::default()
+    }
+
+    /// An empty graph with room for `entities` entities and `relationships` relationships.
+    pub fn with_capacity(entities: usize, relationships: usize) -> Self {
+        let mut graph = Self::new();
+        graph.reserve_entities(entities);
+        graph.reserve_relationships(relationships);
+        graph
+    }
+
+    pub fn reserve_entities(&mut self, additional: usize) {
+        self.entities.reserve(additional);
+    }
+
+    pub fn reserve_relationships(&mut self, additional: usize) {
+        self.relationships.reserve(additional);
+        self.incoming.reserve(additional);
+    }
+
+    /// Makes room for `additional` more edges of `relationship_key` leaving `from`. Does
+    /// nothing if `from` has no such edge yet, since empty neighbor lists aren't kept.
+    pub fn reserve_edges(&mut self, relationship_key: &R, from: &ID, additional: usize) {
+        if let Some(neighbors) = self
+            .relationships
+            .get_mut(relationship_key)
+            .and_then(|adjacency_list| adjacency_list.edges.get_mut(from))
+        {
+            neighbors.reserve(additional);
         }
     }
 
     pub fn add_entity(
         &mut self,
         id: ID,
-        components: HashMap<K, Value>,
+        components: Components<K>,
     ) -> Result<(), EntityGraphError> {
         if self.entities.contains_key(&id) {
-            return Err(EntityGraphError::EntityAlreadyExists);
+            return Err(EntityGraphError::entity_already_exists(&id));
         }
+        self.changes.touch(&id);
         self.entities.insert(id, components);
         Ok(())
     }
 
+    /// Adds every entity, stopping at the first ID that already exists. Entities before it
+    /// stay in the graph.
+    pub fn add_entities(
+        &mut self,
+        entities: impl IntoIterator<Item = (ID, Components<K>)>,
+    ) -> Result<(), EntityGraphError> {
+        let entities = entities.into_iter();
+        self.entities.reserve(entities.size_hint().0);
+        for (id, components) in entities {
+            self.add_entity(id, components)?;
+        }
+        Ok(())
+    }
+
     pub fn remove_entity(&mut self, id: &ID) {
-        // Remove the entity from the entities HashMap
-        self.entities.remove(id);
+        // Remove the entity from the entities map
+        if self.entities.shift_remove(id).is_some() {
+            self.changes.remove(id);
+        }
+
+        for label in self.labels.shift_remove(id).unwrap_or_default() {
+            self.unindex_label(id, &label);
+        }
+
+        self.remove_outgoing_edges(id);
+
+        // Remove the entity from the neighbor lists of every entity pointing at it
+        for (relationship, adjacency_list) in self.incoming.iter_mut() {
+            let Some(sources) = adjacency_list.edges.shift_remove(id) else {
+                continue;
+            };
+            let Some(outgoing) = self.relationships.get_mut(relationship) else {
+                continue;
+            };
+            for source in sources {
+                if let Some(neighbors) = outgoing.edges.get_mut(&source) {
+                    neighbors.retain(|neighbor_id| neighbor_id != id);
+                    if neighbors.is_empty() {
+                        outgoing.edges.shift_remove(&source);
+                    }
+                }
+                outgoing.remove_edge_data(&source, id);
+                self.changes.touch(&source);
+            }
+        }
+        self.incoming
+            .retain(|_, adjacency_list| !adjacency_list.edges.is_empty());
+    }
 
-        // Remove the entity from all relationships in the relationships HashMap
-        for (_relationship_key, adjacency_list) in &mut self.relationships {
-            adjacency_list.edges.remove(id);
-            // Additionally, remove the entity from the list of neighbors in all adjacency lists
-            for neighbors in adjacency_list.edges.values_mut() {
-                neighbors.retain(|neighbor_id| neighbor_id != id);
+    /// Removes every edge leaving `id`, in every relationship.
+    pub(crate) fn remove_outgoing_edges(&mut self, id: &ID) {
+        for (relationship, adjacency_list) in self.relationships.iter_mut() {
+            adjacency_list.data.shift_remove(id);
+            for to in adjacency_list.edges.shift_remove(id).unwrap_or_default() {
+                remove_first_edge(&mut self.incoming, relationship, &to, id);
             }
         }
     }
@@ -98,126 +680,896 @@ where
         from: ID,
         to: ID,
     ) -> Result<(), EntityGraphError> {
-        if !self.entities.contains_key(&from) || !self.entities.contains_key(&to) {
-            return Err(EntityGraphError::EdgeError);
+        for (endpoint, id) in [(Endpoint::Source, &from), (Endpoint::Target, &to)] {
+            if !self.entities.contains_key(id) {
+                return Err(EntityGraphError::EdgeError {
+                    relationship: relationship_key.to_string(),
+                    endpoint,
+                    id: id_string(id),
+                });
+            }
         }
 
-        // Get or create the adjacency list for the given relationship_key
-        let adjacency_list = self
-            .relationships
-            .entry(relationship_key)
-            .or_insert_with(|| AdjacencyList {
-                edges: HashMap::new(),
-            });
+        if self.contains_edge(&relationship_key, &from, &to) {
+            match self.duplicate_edge_policy(&relationship_key) {
+                DuplicateEdgePolicy::AllowParallel => {}
+                DuplicateEdgePolicy::DedupSilently => return Ok(()),
+                DuplicateEdgePolicy::ErrorOnDuplicate => {
+                    return Err(EntityGraphError::DuplicateEdge {
+                        relationship: relationship_key.to_string(),
+                        from: id_string(&from),
+                        to: id_string(&to),
+                    })
+                }
+            }
+        }
 
-        // Add the edge to the adjacency list
-        adjacency_list
-            .edges
-            .entry(from)
-            .or_insert_with(Vec::new)
-            .push(to);
+        self.changes.touch(&from);
+        insert_edge(
+            &mut self.incoming,
+            relationship_key.clone(),
+            to.clone(),
+            from.clone(),
+        );
+        insert_edge(&mut self.relationships, relationship_key, from, to);
 
         Ok(())
     }
 
-    pub fn serialize(&self) -> Result<String, Box<dyn Error>> {
-        serde_json::to_string(&self).map_err(Into::into)
-    }
-
-    pub fn deserialize_with_registry(
-        data: &str,
-        registry: &TypeRegistry,
-    ) -> Result<Self, EntityGraphError> {
-        let mut graph: Self = serde_json::from_str(data).map_err(|e| {
-            EntityGraphError::DeserializationError(format!("Failed to deserialize graph: {}", e))
-        })?;
-
-        // Deserialize components
-        for (_id, component_map) in graph.entities.iter_mut() {
-            for (type_name, value) in component_map.iter_mut() {
-                match registry.deserialize_value(&type_name.to_string(), value) {
-                    Ok(new_value) => *value = new_value,
-                    Err(e) => {
-                        return Err(EntityGraphError::DeserializationError(format!(
-                            "Failed to deserialize component: {}",
-                            e
-                        )))
-                    }
-                }
-            }
+    /// Adds a `from -> to` edge of `relationship_key` for every pair, stopping at the first
+    /// one `add_edge` rejects. Edges before it stay in the graph.
+    pub fn add_edges(
+        &mut self,
+        relationship_key: R,
+        edges: impl IntoIterator<Item = (ID, ID)>,
+    ) -> Result<(), EntityGraphError> {
+        for (from, to) in edges {
+            self.add_edge(relationship_key.clone(), from, to)?;
         }
+        Ok(())
+    }
 
-        Ok(graph)
+    /// Whether there is at least one `from -> to` edge of the given relationship.
+    pub fn contains_edge(&self, relationship_key: &R, from: &ID, to: &ID) -> bool {
+        self.relationships
+            .get(relationship_key)
+            .and_then(|adjacency_list| adjacency_list.edges.get(from))
+            .is_some_and(|neighbors| neighbors.contains(to))
     }
-    pub fn traverse_dfs(&self, start: ID) -> Option<Vec<ID>> {
-        let mut visited = HashMap::new();
-        let mut stack = vec![start];
-        let mut result = Vec::new();
 
-        while let Some(current) = stack.pop() {
-            if !visited.contains_key(&current) {
-                visited.insert(current.clone(), true);
-                result.push(current.clone());
-
-                if let Some(neighbors) = self.get_neighbors(&current) {
-                    for neighbor in neighbors {
-                        if !visited.contains_key(neighbor) {
-                            stack.push(neighbor.clone());
-                        }
-                    }
-                }
-            }
-        }
+    /// What `add_edge` does with an edge of `relationship_key` between entities that already
+    /// have one. Relationships allow parallel edges unless configured otherwise.
+    pub fn duplicate_edge_policy(&self, relationship_key: &R) -> DuplicateEdgePolicy {
+        self.edge_policies
+            .get(relationship_key)
+            .copied()
+            .unwrap_or_default()
+    }
 
-        if result.is_empty() {
-            None
+    /// Sets the duplicate edge policy of a relationship. Edges already in the graph are kept.
+    pub fn set_duplicate_edge_policy(&mut self, relationship_key: R, policy: DuplicateEdgePolicy) {
+        if policy == DuplicateEdgePolicy::default() {
+            self.edge_policies.shift_remove(&relationship_key);
         } else {
-            Some(result)
+            self.edge_policies.insert(relationship_key, policy);
         }
     }
 
-    pub fn traverse_bfs(&self, start: ID) -> Option<Vec<ID>> {
-        let mut visited = HashMap::new();
-        let mut queue = VecDeque::new();
-        let mut result = Vec::new();
+    /// Adds a `from -> to` edge carrying `data`, such as a weight, timestamp or label. If the
+    /// edge already exists and the relationship's policy allows it, `data` replaces the payload
+    /// the edges share.
+    pub fn add_edge_with_data(
+        &mut self,
+        relationship_key: R,
+        from: ID,
+        to: ID,
+        data: Value,
+    ) -> Result<(), EntityGraphError> {
+        self.add_edge(relationship_key.clone(), from.clone(), to.clone())?;
+        self.relationships
+            .get_mut(&relationship_key)
+            .unwrap()
+            .set_edge_data(from, to, data);
+        Ok(())
+    }
 
-        queue.push_back(start.clone());
-        visited.insert(start.clone(), true);
+    /// Adds an edge with `data` if there is some, such as when copying an edge from another
+    /// graph.
+    pub(crate) fn add_edge_with_optional_data(
+        &mut self,
+        relationship_key: R,
+        from: ID,
+        to: ID,
+        data: Option<Value>,
+    ) -> Result<(), EntityGraphError> {
+        match data {
+            Some(data) => self.add_edge_with_data(relationship_key, from, to, data),
+            None => self.add_edge(relationship_key, from, to),
+        }
+    }
 
-        while let Some(current) = queue.pop_front() {
-            result.push(current.clone());
+    /// The payload of the `from -> to` edge, if it has one.
+    pub fn get_edge_data(&self, relationship_key: &R, from: &ID, to: &ID) -> Option<&Value> {
+        self.relationships
+            .get(relationship_key)?
+            .edge_data(from, to)
+    }
 
-            if let Some(neighbors) = self.get_neighbors(&current) {
-                for neighbor in neighbors {
-                    if !visited.contains_key(neighbor) {
-                        visited.insert(neighbor.clone(), true);
-                        queue.push_back(neighbor.clone());
-                    }
-                }
-            }
+    /// Replaces the payload of an existing `from -> to` edge, returning the previous one.
+    pub fn set_edge_data(
+        &mut self,
+        relationship_key: &R,
+        from: &ID,
+        to: &ID,
+        data: Value,
+    ) -> Result<Option<Value>, EntityGraphError> {
+        let adjacency_list = self
+            .relationships
+            .get_mut(relationship_key)
+            .ok_or_else(|| EntityGraphError::relationship_not_found(relationship_key))?;
+        if !adjacency_list
+            .edges
+            .get(from)
+            .is_some_and(|neighbors| neighbors.contains(to))
+        {
+            return Err(EntityGraphError::EdgeNotFound {
+                relationship: relationship_key.to_string(),
+                from: id_string(from),
+                to: id_string(to),
+            });
         }
+        let previous = adjacency_list.set_edge_data(from.clone(), to.clone(), data);
+        self.changes.touch(from);
+        Ok(previous)
+    }
 
-        if result.is_empty() {
-            None
-        } else {
-            Some(result)
+    /// Removes one `from -> to` edge of the given relationship. If the edge was added more than
+    /// once, only the first occurrence is removed.
+    pub fn remove_edge(
+        &mut self,
+        relationship_key: &R,
+        from: &ID,
+        to: &ID,
+    ) -> Result<(), EntityGraphError> {
+        if !self.relationships.contains_key(relationship_key) {
+            return Err(EntityGraphError::relationship_not_found(relationship_key));
+        }
+        if !remove_first_edge(&mut self.relationships, relationship_key, from, to) {
+            return Err(EntityGraphError::EdgeNotFound {
+                relationship: relationship_key.to_string(),
+                from: id_string(from),
+                to: id_string(to),
+            });
         }
+        remove_first_edge(&mut self.incoming, relationship_key, to, from);
+        self.changes.touch(from);
+        Ok(())
     }
 
-    pub fn get_neighbors(&self, entity_id: &ID) -> Option<&Vec<ID>> {
-        for adjacency_list in self.relationships.values() {
-            if let Some(neighbors) = adjacency_list.edges.get(entity_id) {
-                return Some(neighbors);
-            }
+    /// Removes every edge of the given relationship.
+    pub fn remove_relationship(&mut self, relationship_key: &R) -> Result<(), EntityGraphError> {
+        let adjacency_list = self
+            .relationships
+            .shift_remove(relationship_key)
+            .ok_or_else(|| EntityGraphError::relationship_not_found(relationship_key))?;
+        self.incoming.shift_remove(relationship_key);
+        for from in adjacency_list.edges.keys() {
+            self.changes.touch(from);
         }
-        None
+        Ok(())
     }
 
-    pub fn get_component(&self, entity_id: &ID, component_key: &K) -> Option<&Value> {
+    pub fn serialize(&self) -> Result<String, Box<dyn Error>> {
+        serde_json::to_string(&self).map_err(Into::into)
+    }
+
+    /// Like `serialize`, indented for reading.
+    pub fn serialize_pretty(&self) -> Result<String, EntityGraphError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| EntityGraphError::SerializationError(e.to_string()))
+    }
+
+    /// Like `serialize_pretty`, with object keys, each entity's neighbors and labels sorted,
+    /// so graphs that compare equal serialize identically whatever order they were built in.
+    pub fn serialize_canonical(&self) -> Result<String, EntityGraphError> {
+        let sort = |values: &mut Value| {
+            if let Value::Array(values) = values {
+                values.sort_by_cached_key(Value::to_string);
+            }
+        };
+        // Objects in `Value` keep their keys sorted.
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| EntityGraphError::SerializationError(e.to_string()))?;
+        if let Some(Value::Object(relationships)) = value.get_mut("relationships") {
+            for adjacency_list in relationships.values_mut() {
+                if let Some(Value::Object(edges)) = adjacency_list.get_mut("edges") {
+                    edges.values_mut().for_each(sort);
+                }
+            }
+        }
+        if let Some(Value::Object(labels)) = value.get_mut("labels") {
+            labels.values_mut().for_each(sort);
+        }
+        serde_json::to_string_pretty(&value)
+            .map_err(|e| EntityGraphError::SerializationError(e.to_string()))
+    }
+
+    /// Writes the serialized graph to `writer` as it's produced, without building the whole
+    /// string in memory first.
+    pub fn serialize_to_writer(&self, writer: impl Write) -> Result<(), EntityGraphError> {
+        let mut writer = BufWriter::new(writer);
+        serde_json::to_writer(&mut writer, self)
+            .map_err(|e| EntityGraphError::SerializationError(e.to_string()))?;
+        writer
+            .flush()
+            .map_err(|e| EntityGraphError::SerializationError(e.to_string()))
+    }
+
+    /// Like `deserialize_with_registry`, reading entities from `reader` as they arrive rather
+    /// than from a string holding the whole graph. Envelopes written by `serialize_versioned`
+    /// or `serialize_with_checksum` are read whole, as their checksum covers the entire graph.
+    pub fn deserialize_from_reader(
+        reader: impl Read,
+        registry: &TypeRegistry,
+    ) -> Result<Self, EntityGraphError> {
+        let read_error = |e: std::io::Error| EntityGraphError::DeserializationError(e.to_string());
+        let mut reader = BufReader::new(reader);
+        // Enough of the start to see whether the first key is `format_version`.
+        let mut prefix = Vec::new();
+        let mut significant = 0;
+        while significant < "{\"format_version\"".len() {
+            let mut byte = [0];
+            if reader.read(&mut byte).map_err(read_error)? == 0 {
+                break;
+            }
+            prefix.push(byte[0]);
+            if !byte[0].is_ascii_whitespace() {
+                significant += 1;
+            }
+        }
+        if envelope::is_envelope(&String::from_utf8_lossy(&prefix)) {
+            reader.read_to_end(&mut prefix).map_err(read_error)?;
+            let data = String::from_utf8(prefix)
+                .map_err(|e| EntityGraphError::DeserializationError(e.to_string()))?;
+            return Self::deserialize_with_registry(&data, registry);
+        }
+
+        let graph: Self =
+            serde_json::from_reader(prefix.as_slice().chain(reader)).map_err(|e| {
+                EntityGraphError::DeserializationError(format!(
+                    "Failed to deserialize graph: {}",
+                    e
+                ))
+            })?;
+        graph
+            .apply_registry(registry, registry.unknown_component_policy())
+            .map(|(graph, _)| graph)
+    }
+
+    /// Reads a graph written by `serialize`, or by `serialize_versioned` or
+    /// `serialize_with_checksum` with the current format version, verifying any checksum.
+    /// Components whose key has no type registered in `registry` are handled according to
+    /// the registry's `unknown_component_policy`, failing by default.
+    pub fn deserialize_with_registry(
+        data: &str,
+        registry: &TypeRegistry,
+    ) -> Result<Self, EntityGraphError> {
+        Self::deserialize_with_unknown_components(
+            data,
+            registry,
+            registry.unknown_component_policy(),
+        )
+        .map(|(graph, _)| graph)
+    }
+
+    /// Like `deserialize_with_registry`, handling components whose key isn't registered
+    /// according to `policy` instead of the registry's policy. Returns the graph with the
+    /// (entity, key) pairs of those components, which is empty with
+    /// `UnknownComponentPolicy::Error`.
+    pub fn deserialize_with_unknown_components(
+        data: &str,
+        registry: &TypeRegistry,
+        policy: UnknownComponentPolicy,
+    ) -> Result<(Self, Vec<(ID, K)>), EntityGraphError> {
+        let data = if envelope::is_envelope(data) {
+            let (version, graph) = envelope::open(data)?;
+            if version != FORMAT_VERSION {
+                return Err(EntityGraphError::DeserializationError(format!(
+                    "Format version {} needs migrating with deserialize_versioned",
+                    version
+                )));
+            }
+            graph
+        } else {
+            data
+        };
+        let graph: Self = serde_json::from_str(data).map_err(|e| {
+            EntityGraphError::DeserializationError(format!("Failed to deserialize graph: {}", e))
+        })?;
+        graph.apply_registry(registry, policy)
+    }
+
+    /// Builds a graph from its serialized form as a JSON value, checking every component
+    /// against `registry` like `deserialize_with_registry`.
+    pub(crate) fn from_value_with_registry(
+        value: Value,
+        registry: &TypeRegistry,
+    ) -> Result<Self, EntityGraphError> {
+        let graph: Self = serde_json::from_value(value).map_err(|e| {
+            EntityGraphError::DeserializationError(format!("Failed to deserialize graph: {}", e))
+        })?;
+        graph
+            .apply_registry(registry, registry.unknown_component_policy())
+            .map(|(graph, _)| graph)
+    }
+
+    /// Passes every component of a freshly loaded graph through `registry`, as
+    /// `deserialize_with_unknown_components` does.
+    pub(crate) fn apply_registry(
+        mut self,
+        registry: &TypeRegistry,
+        policy: UnknownComponentPolicy,
+    ) -> Result<(Self, Vec<(ID, K)>), EntityGraphError> {
+        // Deserialize components
+        let mut unknown = Vec::new();
+        for (id, component_map) in self.entities.iter_mut() {
+            let mut skipped = Vec::new();
+            for (type_name, value) in component_map.iter_mut() {
+                let name = type_name.to_string();
+                if policy != UnknownComponentPolicy::Error
+                    && !registry.deserialize_fn_map.contains_key(&name)
+                {
+                    unknown.push((id.clone(), type_name.clone()));
+                    if policy == UnknownComponentPolicy::SkipUnknown {
+                        skipped.push(type_name.clone());
+                    }
+                    continue;
+                }
+                match registry.deserialize_value(&name, value) {
+                    Ok(new_value) => *value = new_value,
+                    Err(e) => {
+                        return Err(EntityGraphError::DeserializationError(format!(
+                            "Failed to deserialize component: {}",
+                            e
+                        )))
+                    }
+                }
+            }
+            for type_name in skipped {
+                component_map.shift_remove(&type_name);
+            }
+        }
+
+        Ok((self, unknown))
+    }
+
+    /// Like `deserialize_with_registry`, for a graph saved with `OldId` IDs. Every ID is
+    /// converted with `map_id`, which is called once per distinct ID. Fails if two IDs map to
+    /// the same new ID.
+    pub fn deserialize_with_id_mapper<OldId, F>(
+        data: &str,
+        registry: &TypeRegistry,
+        mut map_id: F,
+    ) -> Result<Self, EntityGraphError>
+    where
+        OldId: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+        F: FnMut(&OldId) -> ID,
+    {
+        let saved = EntityGraph::<OldId, K, R>::deserialize_with_registry(data, registry)?;
+        let mut mapping = HashMap::with_capacity(saved.entities.len());
+        let mut map = |id: &OldId| {
+            mapping
+                .entry(id.clone())
+                .or_insert_with(|| map_id(id))
+                .clone()
+        };
+
+        let mut graph = Self::new();
+        graph.edge_policies = saved.edge_policies;
+        graph.metadata = saved.metadata;
+        graph.add_entities(
+            saved
+                .entities
+                .into_iter()
+                .map(|(id, components)| (map(&id), components)),
+        )?;
+        for (id, labels) in saved.labels {
+            let id = map(&id);
+            for label in labels {
+                graph.add_label(&id, label)?;
+            }
+        }
+        for (relationship, adjacency_list) in saved.relationships {
+            for (from, neighbors) in &adjacency_list.edges {
+                for to in neighbors {
+                    graph.add_edge_with_optional_data(
+                        relationship.clone(),
+                        map(from),
+                        map(to),
+                        adjacency_list.edge_data(from, to).cloned(),
+                    )?;
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn traverse_dfs(&self, start: ID) -> Option<Vec<ID>> {
+        Some(self.iter_dfs(start).collect())
+    }
+
+    pub fn traverse_bfs(&self, start: ID) -> Option<Vec<ID>> {
+        Some(self.iter_bfs(start).collect())
+    }
+
+    /// Depth-first traversal from `start` that only follows edges of `relationships` (or every
+    /// relationship if `None`) and stops descending at `max_depth`. Each visited ID is returned
+    /// with its depth, `start` being at depth 0.
+    pub fn traverse_dfs_limited(
+        &self,
+        start: ID,
+        relationships: Option<&[R]>,
+        max_depth: Option<usize>,
+    ) -> Vec<(ID, usize)> {
+        let options = TraversalOptions {
+            max_depth,
+            relationships: relationships.map(<[R]>::to_vec),
+            ..TraversalOptions::default()
+        };
+        self.traverse_dfs_with(start, &options)
+    }
+
+    /// Breadth-first counterpart of `traverse_dfs_limited`. Depths are shortest distances over
+    /// the followed relationships.
+    pub fn traverse_bfs_limited(
+        &self,
+        start: ID,
+        relationships: Option<&[R]>,
+        max_depth: Option<usize>,
+    ) -> Vec<(ID, usize)> {
+        let options = TraversalOptions {
+            max_depth,
+            relationships: relationships.map(<[R]>::to_vec),
+            ..TraversalOptions::default()
+        };
+        self.traverse_bfs_with(start, &options)
+    }
+
+    /// Follows one relationship per hop, in order, and returns the entities reached at each hop.
+    /// Each level lists entities once, in the order they were reached.
+    pub fn traverse_path(&self, start: &ID, path: &[R]) -> Vec<Vec<ID>> {
+        let mut levels = Vec::with_capacity(path.len());
+        let mut current = vec![start.clone()];
+        for relationship in path {
+            let mut seen = HashSet::new();
+            let next = current
+                .iter()
+                .filter_map(|id| self.get_neighbors_by_relationship(id, relationship))
+                .flatten()
+                .filter(|id| seen.insert(*id))
+                .cloned()
+                .collect::<Vec<_>>();
+            levels.push(next.clone());
+            current = next;
+        }
+        levels
+    }
+
+    pub(crate) fn filtered_neighbors<'a>(
+        &'a self,
+        id: &ID,
+        relationships: Option<&[R]>,
+    ) -> Vec<&'a ID> {
+        match relationships {
+            Some(relationships) => relationships
+                .iter()
+                .filter_map(|relationship| self.get_neighbors_by_relationship(id, relationship))
+                .flatten()
+                .collect(),
+            None => self.get_neighbors(id).unwrap_or_default(),
+        }
+    }
+
+    /// A lazy depth-first traversal from `start` over every relationship.
+    pub fn iter_dfs(&self, start: ID) -> Dfs<'_, ID, K, R> {
+        Dfs {
+            graph: self,
+            visited: HashSet::new(),
+            stack: vec![start],
+        }
+    }
+
+    /// A lazy breadth-first traversal from `start` over every relationship.
+    pub fn iter_bfs(&self, start: ID) -> Bfs<'_, ID, K, R> {
+        Bfs {
+            graph: self,
+            visited: HashSet::from([start.clone()]),
+            queue: VecDeque::from([start]),
+        }
+    }
+
+    /// Outgoing neighbors of an entity across every relationship, or `None` if it has no
+    /// outgoing edges. Neighbors appear once per edge; the order of relationships is
+    /// unspecified.
+    pub fn get_neighbors(&self, entity_id: &ID) -> Option<Vec<&ID>> {
+        let neighbors = self
+            .relationships
+            .values()
+            .filter_map(|adjacency_list| adjacency_list.edges.get(entity_id))
+            .flatten()
+            .collect::<Vec<_>>();
+        (!neighbors.is_empty()).then_some(neighbors)
+    }
+
+    /// Outgoing neighbors of an entity over one relationship, in insertion order.
+    pub fn get_neighbors_by_relationship(
+        &self,
+        entity_id: &ID,
+        relationship_key: &R,
+    ) -> Option<&Vec<ID>> {
+        self.relationships
+            .get(relationship_key)
+            .and_then(|adjacency_list| adjacency_list.edges.get(entity_id))
+    }
+
+    /// Entities with an edge pointing at `entity_id`, across every relationship, or `None` if
+    /// there are none. Sources appear once per edge; the order of relationships is unspecified.
+    pub fn get_incoming_neighbors(&self, entity_id: &ID) -> Option<Vec<&ID>> {
+        let neighbors = self
+            .incoming
+            .values()
+            .filter_map(|adjacency_list| adjacency_list.edges.get(entity_id))
+            .flatten()
+            .collect::<Vec<_>>();
+        (!neighbors.is_empty()).then_some(neighbors)
+    }
+
+    /// Entities with an edge of one relationship pointing at `entity_id`, in insertion order.
+    pub fn get_incoming_neighbors_by_relationship(
+        &self,
+        entity_id: &ID,
+        relationship_key: &R,
+    ) -> Option<&Vec<ID>> {
+        self.incoming
+            .get(relationship_key)
+            .and_then(|adjacency_list| adjacency_list.edges.get(entity_id))
+    }
+
+    /// Every entity that can't be reached from `roots` by following edges of the given
+    /// relationships. Roots that aren't entities are ignored.
+    pub fn find_unreachable(&self, roots: &[ID], relationships: &[R]) -> Vec<ID> {
+        let mut reachable = HashSet::new();
+        let mut stack = roots
+            .iter()
+            .filter(|root| self.entities.contains_key(*root))
+            .collect::<Vec<_>>();
+        while let Some(current) = stack.pop() {
+            if !reachable.insert(current) {
+                continue;
+            }
+            for relationship in relationships {
+                if let Some(neighbors) = self.get_neighbors_by_relationship(current, relationship) {
+                    stack.extend(neighbors.iter().filter(|id| !reachable.contains(id)));
+                }
+            }
+        }
+        self.entities
+            .keys()
+            .filter(|id| !reachable.contains(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes every entity returned by `find_unreachable`, along with their edges and labels,
+    /// and returns their IDs.
+    pub fn collect_unreachable(&mut self, roots: &[ID], relationships: &[R]) -> Vec<ID> {
+        let unreachable = self.find_unreachable(roots, relationships);
+        for id in &unreachable {
+            self.remove_entity(id);
+        }
+        unreachable
+    }
+
+    /// Every entity with its components, in iteration order.
+    pub fn entities(&self) -> impl Iterator<Item = (&ID, &Components<K>)> + '_ {
+        self.entities.iter()
+    }
+
+    pub fn entity_ids(&self) -> impl Iterator<Item = &ID> + '_ {
+        self.entities.keys()
+    }
+
+    pub fn components(&self, entity_id: &ID) -> Option<&Components<K>> {
+        self.entities.get(entity_id)
+    }
+
+    /// Every relationship with at least one edge.
+    pub fn relationships(&self) -> impl Iterator<Item = &R> + '_ {
+        self.relationships
+            .iter()
+            .filter(|(_, adjacency_list)| !adjacency_list.edges.is_empty())
+            .map(|(relationship, _)| relationship)
+    }
+
+    /// Every `(from, to)` edge of a relationship, parallel edges included.
+    pub fn edges<'a>(
+        &'a self,
+        relationship_key: &R,
+    ) -> impl Iterator<Item = (&'a ID, &'a ID)> + 'a {
+        self.relationships
+            .get(relationship_key)
+            .into_iter()
+            .flat_map(|adjacency_list| &adjacency_list.edges)
+            .flat_map(|(from, neighbors)| neighbors.iter().map(move |to| (from, to)))
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// The number of edges over every relationship, parallel edges included.
+    pub fn edge_count(&self) -> usize {
+        self.relationships
+            .values()
+            .flat_map(|adjacency_list| adjacency_list.edges.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn get_component(&self, entity_id: &ID, component_key: &K) -> Option<&Value> {
         self.entities
             .get(entity_id)
             .and_then(|components| components.get(component_key))
     }
+
+    /// Inserts the entity, or replaces the components of an existing one (keeping its labels
+    /// and edges). Returns the previous components, if any.
+    pub fn upsert_entity(&mut self, id: ID, components: Components<K>) -> Option<Components<K>> {
+        self.changes.touch(&id);
+        self.entities.insert(id, components)
+    }
+
+    /// Adds a component to an existing entity. Fails if the entity already has one with the
+    /// same key.
+    pub fn add_component(
+        &mut self,
+        entity_id: &ID,
+        component_key: K,
+        value: Value,
+    ) -> Result<(), EntityGraphError> {
+        let components = self
+            .entities
+            .get_mut(entity_id)
+            .ok_or_else(|| EntityGraphError::entity_not_found(entity_id))?;
+        if components.contains_key(&component_key) {
+            return Err(EntityGraphError::ComponentAlreadyExists {
+                id: id_string(entity_id),
+                component: component_key.to_string(),
+            });
+        }
+        components.insert(component_key, value);
+        self.changes.touch(entity_id);
+        Ok(())
+    }
+
+    /// Replaces an existing component, returning its previous value.
+    pub fn update_component(
+        &mut self,
+        entity_id: &ID,
+        component_key: &K,
+        value: Value,
+    ) -> Result<Value, EntityGraphError> {
+        let component = self
+            .entities
+            .get_mut(entity_id)
+            .ok_or_else(|| EntityGraphError::entity_not_found(entity_id))?
+            .get_mut(component_key)
+            .ok_or_else(|| EntityGraphError::component_not_found(entity_id, component_key))?;
+        let previous = std::mem::replace(component, value);
+        self.changes.touch(entity_id);
+        Ok(previous)
+    }
+
+    /// Removes a component from an entity, returning its value.
+    pub fn remove_component(
+        &mut self,
+        entity_id: &ID,
+        component_key: &K,
+    ) -> Result<Value, EntityGraphError> {
+        let value = self
+            .entities
+            .get_mut(entity_id)
+            .ok_or_else(|| EntityGraphError::entity_not_found(entity_id))?
+            .shift_remove(component_key)
+            .ok_or_else(|| EntityGraphError::component_not_found(entity_id, component_key))?;
+        self.changes.touch(entity_id);
+        Ok(value)
+    }
+
+    /// The `component_key` component of an entity deserialized as `T`, or `None` if the entity
+    /// doesn't have it.
+    pub fn get_component_typed<T: DeserializeOwned>(
+        &self,
+        entity_id: &ID,
+        component_key: &K,
+    ) -> Result<Option<T>, EntityGraphError> {
+        self.get_component(entity_id, component_key)
+            .map(|value| {
+                T::deserialize(value).map_err(|e| {
+                    EntityGraphError::DeserializationError(format!(
+                        "Failed to deserialize component {}: {}",
+                        component_key, e
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Serializes `component` and stores it as the entity's `component_key` component,
+    /// replacing any previous value.
+    pub fn set_component_typed<T: Serialize>(
+        &mut self,
+        entity_id: &ID,
+        component_key: K,
+        component: &T,
+    ) -> Result<(), EntityGraphError> {
+        let value = serde_json::to_value(component).map_err(|e| {
+            EntityGraphError::SerializationError(format!(
+                "Failed to serialize component {}: {}",
+                component_key, e
+            ))
+        })?;
+        let components = self
+            .entities
+            .get_mut(entity_id)
+            .ok_or_else(|| EntityGraphError::entity_not_found(entity_id))?;
+        components.insert(component_key, value);
+        self.changes.touch(entity_id);
+        Ok(())
+    }
+
+    /// Replaces the `component_key` component of every entity that has one with
+    /// `transform(value)`. Entities whose value actually changed are recorded as changed (see
+    /// `export_changes_since`) and returned.
+    pub fn transform_components<F>(&mut self, component_key: &K, mut transform: F) -> Vec<ID>
+    where
+        F: FnMut(&Value) -> Value,
+    {
+        let mut changed = Vec::new();
+        for (id, components) in self.entities.iter_mut() {
+            let Some(value) = components.get_mut(component_key) else {
+                continue;
+            };
+            let new_value = transform(value);
+            if new_value != *value {
+                *value = new_value;
+                changed.push(id.clone());
+            }
+        }
+        for id in &changed {
+            self.changes.touch(id);
+        }
+        changed
+    }
+
+    /// Attaches `label` to an entity. Returns `false` if the entity already had it.
+    pub fn add_label(
+        &mut self,
+        id: &ID,
+        label: impl Into<String>,
+    ) -> Result<bool, EntityGraphError> {
+        if !self.entities.contains_key(id) {
+            return Err(EntityGraphError::entity_not_found(id));
+        }
+        let label = label.into();
+        self.changes.touch(id);
+        let added = self
+            .labels
+            .entry(id.clone())
+            .or_default()
+            .insert(label.clone());
+        self.label_index
+            .entry(label)
+            .or_default()
+            .insert(id.clone());
+        Ok(added)
+    }
+
+    /// Detaches `label` from an entity. Returns `false` if the entity did not have it.
+    pub fn remove_label(&mut self, id: &ID, label: &str) -> bool {
+        let Some(labels) = self.labels.get_mut(id) else {
+            return false;
+        };
+        let removed = labels.shift_remove(label);
+        if labels.is_empty() {
+            self.labels.shift_remove(id);
+        }
+        if removed {
+            self.changes.touch(id);
+            self.unindex_label(id, label);
+        }
+        removed
+    }
+
+    fn unindex_label(&mut self, id: &ID, label: &str) {
+        if let Some(ids) = self.label_index.get_mut(label) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.label_index.remove(label);
+            }
+        }
+    }
+
+    pub fn labels(&self, id: &ID) -> Option<&LabelSet> {
+        self.labels.get(id)
+    }
+
+    pub fn has_label(&self, id: &ID, label: &str) -> bool {
+        self.labels
+            .get(id)
+            .is_some_and(|labels| labels.contains(label))
+    }
+
+    /// Every entity carrying `label`, looked up through the label index.
+    pub fn entities_with_label<'a>(&'a self, label: &str) -> impl Iterator<Item = &'a ID> + 'a {
+        self.label_index.get(label).into_iter().flatten()
+    }
+
+    /// IDs of every entity whose components satisfy `predicate`, in no particular order.
+    pub fn find_entities<F>(&self, mut predicate: F) -> Vec<ID>
+    where
+        F: FnMut(&ID, &Components<K>) -> bool,
+    {
+        self.entities
+            .iter()
+            .filter(|(id, components)| predicate(id, components))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// One page of the entities matching `predicate`, sorted by `ordering`.
+    /// Only `offset + limit` matches are held in memory at once.
+    pub fn find_entities_paged<F, O>(
+        &self,
+        mut predicate: F,
+        mut ordering: O,
+        offset: usize,
+        limit: usize,
+    ) -> Page<ID>
+    where
+        F: FnMut(&ID, &Components<K>) -> bool,
+        O: FnMut(&ID, &ID) -> std::cmp::Ordering,
+    {
+        let window = offset.saturating_add(limit);
+        let mut best: Vec<&ID> = Vec::new();
+        let mut total_matches = 0;
+        for (id, components) in &self.entities {
+            if !predicate(id, components) {
+                continue;
+            }
+            total_matches += 1;
+            if window == 0 {
+                continue;
+            }
+            let position = best.partition_point(|other| ordering(other, id).is_le());
+            if position < window {
+                best.insert(position, id);
+                best.truncate(window);
+            }
+        }
+        Page {
+            items: best.into_iter().skip(offset).cloned().collect(),
+            total_matches,
+        }
+    }
+}
+
+/// A window into a larger, ordered result set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The number of results across all pages.
+    pub total_matches: usize,
 }
 
 #[cfg(feature = "petgraph")]
@@ -245,147 +1597,1225 @@ fn entity_graph_to_petgraph_directed_graphs<
             }
         }
 
-        graphs.push(graph);
+        graphs.push(graph);
+    }
+
+    graphs
+}
+
+/// What loading a graph through a `TypeRegistry` does with a component whose key isn't
+/// registered, such as one saved by a newer version of the application.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum UnknownComponentPolicy {
+    /// Fail on the first unknown component.
+    #[default]
+    Error,
+    /// Leave the component out of the graph.
+    SkipUnknown,
+    /// Keep the component's value as saved.
+    PreserveRaw,
+}
+
+type DeserializeFn = Box<dyn Fn(&Value) -> Result<Box<dyn Any + Send>, String>>;
+type SerializeFn = Box<dyn Fn(&(dyn Any + Send)) -> Option<Value>>;
+
+#[derive(Default)]
+pub struct TypeRegistry {
+    deserialize_fn_map: HashMap<String, DeserializeFn>,
+    serialize_map: HashMap<String, SerializeFn>,
+    unknown_component_policy: UnknownComponentPolicy,
+    #[cfg(feature = "json-schema")]
+    pub(crate) schemas: HashMap<String, crate::json_schema::ComponentSchema>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a type with its serialization function
+
+    pub fn register<T: 'static + Send + Serialize + DeserializeOwned>(&mut self, type_name: &str) {
+        self.serialize_map.insert(
+            type_name.to_string(),
+            Box::new(move |any: &(dyn Any + Send)| {
+                any.downcast_ref::<T>()
+                    .and_then(|typed_ref| serde_json::to_value(typed_ref).ok())
+            }),
+        );
+
+        self.deserialize_fn_map.insert(
+            type_name.to_string(),
+            Box::new(move |value: &Value| {
+                serde_json::from_value::<T>(value.clone())
+                    .map(|value| Box::new(value) as Box<dyn Any + Send>)
+                    .map_err(|e| e.to_string())
+            }),
+        );
+    }
+
+    /// Registers `New` for `type_name`, upgrading values that only deserialize as `Old` with
+    /// `upgrade`. Registering over an earlier `Old` registration, or an earlier call ending in
+    /// `Old`, chains the upgrades so values of any older version load as `New`.
+    pub fn register_versioned<Old, New>(
+        &mut self,
+        type_name: &str,
+        upgrade: impl Fn(Old) -> New + 'static,
+    ) where
+        Old: 'static + Send + DeserializeOwned,
+        New: 'static + Send + Serialize + DeserializeOwned,
+    {
+        let previous = self.deserialize_fn_map.remove(type_name);
+        self.register::<New>(type_name);
+        let deserialize_old: DeserializeFn = previous.unwrap_or_else(|| {
+            Box::new(|value: &Value| {
+                serde_json::from_value::<Old>(value.clone())
+                    .map(|value| Box::new(value) as Box<dyn Any + Send>)
+                    .map_err(|e| e.to_string())
+            })
+        });
+        self.deserialize_fn_map.insert(
+            type_name.to_string(),
+            Box::new(
+                move |value: &Value| match serde_json::from_value::<New>(value.clone()) {
+                    Ok(value) => Ok(Box::new(value) as Box<dyn Any + Send>),
+                    Err(error) => {
+                        let old = deserialize_old(value).map_err(|_| error.to_string())?;
+                        let old = old
+                            .downcast::<Old>()
+                            .map_err(|_| "Previous version has a different type".to_string())?;
+                        Ok(Box::new(upgrade(*old)) as Box<dyn Any + Send>)
+                    }
+                },
+            ),
+        );
+    }
+
+    /// What `deserialize_with_registry` and the other loaders taking this registry do with
+    /// components whose key isn't registered.
+    pub fn unknown_component_policy(&self) -> UnknownComponentPolicy {
+        self.unknown_component_policy
+    }
+
+    pub fn set_unknown_component_policy(&mut self, policy: UnknownComponentPolicy) {
+        self.unknown_component_policy = policy;
+    }
+
+    /// The names of every registered type, sorted.
+    pub fn registered_types(&self) -> Vec<&str> {
+        let mut names = self
+            .deserialize_fn_map
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.deserialize_fn_map.contains_key(type_name)
+    }
+
+    /// Removes the type registered as `type_name`, returning whether there was one.
+    pub fn unregister(&mut self, type_name: &str) -> bool {
+        self.serialize_map.remove(type_name);
+        #[cfg(feature = "json-schema")]
+        self.schemas.remove(type_name);
+        self.deserialize_fn_map.remove(type_name).is_some()
+    }
+
+    /// Adds every registration in `other`, replacing those of the same name.
+    pub fn merge(&mut self, other: TypeRegistry) -> &mut Self {
+        self.deserialize_fn_map.extend(other.deserialize_fn_map);
+        self.serialize_map.extend(other.serialize_map);
+        #[cfg(feature = "json-schema")]
+        self.schemas.extend(other.schemas);
+        self
+    }
+
+    /// The value deserialized as the type registered for `type_name`.
+    pub fn deserialize_typed(
+        &self,
+        type_name: &str,
+        value: &Value,
+    ) -> Result<Box<dyn Any + Send>, String> {
+        let deserialize_fn = self
+            .deserialize_fn_map
+            .get(type_name)
+            .ok_or_else(|| format!("No deserialization function found for type: {}", type_name))?;
+        deserialize_fn(value)
+    }
+
+    pub fn deserialize_value(&self, type_name: &str, value: &Value) -> Result<Value, String> {
+        // Deserialize using the appropriate function from the map
+        if let Some(deserialize_fn) = self.deserialize_fn_map.get(type_name) {
+            let deserialized_value = deserialize_fn(value);
+
+            // Attempt to re-serialize the deserialized value
+            if let Some(serialize_fn) = self.serialize_map.get(type_name) {
+                serialize_fn(&*deserialized_value?)
+                    .ok_or_else(|| format!("Failed to re-serialize for: {}", type_name))
+            } else {
+                Err(format!(
+                    "No serialization function found for type: {}",
+                    type_name
+                ))
+            }
+        } else {
+            Err(format!(
+                "No deserialization function found for type: {}",
+                type_name
+            ))
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! register_types {
+    ($registry:expr, $(($t:ty, $s:expr)),* ) => {
+        $(
+            $registry.register::<$t>($s);
+        )*
+    };
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::for_kv_map,
+    clippy::unnecessary_to_owned,
+    clippy::useless_vec
+)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn test_add_remove_entity() {
+        let mut graph = TestGraph::new();
+        assert!(graph
+            .add_entity(
+                "entity1".to_string(),
+                vec![
+                    ("component_name1".to_string(), Value::from("component1")),
+                    ("component_name2".to_string(), Value::from("component2"))
+                ]
+                .into_iter()
+                .collect()
+            )
+            .is_ok());
+        assert!(graph
+            .add_entity(
+                "entity1".to_string(),
+                vec![("component_name3".to_string(), Value::from("component3"))]
+                    .into_iter()
+                    .collect()
+            )
+            .is_err());
+
+        graph.remove_entity(&"entity1".to_string());
+        assert_eq!(graph.entities.contains_key(&"entity1".to_string()), false);
+    }
+
+    #[test]
+    fn test_add_edge() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "entity1".to_string(),
+                vec![("component_name1".to_string(), Value::from("component1"))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "entity2".to_string(),
+                vec![("component_name2".to_string(), Value::from("component2"))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+
+        assert!(graph
+            .add_edge(
+                "relationship".to_string(),
+                "entity1".to_string(),
+                "entity2".to_string()
+            )
+            .is_ok());
+        assert!(graph
+            .add_edge(
+                "relationship".to_string(),
+                "entity1".to_string(),
+                "entity3".to_string()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_neighbors_across_relationships() {
+        let mut graph = TestGraph::new();
+        for id in ["entity1", "entity2", "entity3"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        graph
+            .add_edge(
+                "first".to_string(),
+                "entity1".to_string(),
+                "entity2".to_string(),
+            )
+            .unwrap();
+        graph
+            .add_edge(
+                "second".to_string(),
+                "entity1".to_string(),
+                "entity3".to_string(),
+            )
+            .unwrap();
+
+        let mut neighbors = graph.get_neighbors(&"entity1".to_string()).unwrap();
+        neighbors.sort();
+        assert_eq!(neighbors, vec!["entity2", "entity3"]);
+        assert_eq!(
+            graph
+                .get_neighbors_by_relationship(&"entity1".to_string(), &"second".to_string())
+                .unwrap(),
+            &vec!["entity3".to_string()]
+        );
+        assert!(graph
+            .get_neighbors_by_relationship(&"entity2".to_string(), &"first".to_string())
+            .is_none());
+        assert!(graph.get_neighbors(&"entity2".to_string()).is_none());
+        assert_eq!(graph.traverse_bfs("entity1".to_string()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_collect_unreachable() {
+        let mut graph = TestGraph::new();
+        for id in ["root", "child", "grandchild", "stale", "linked"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from, to) in [
+            ("child", "root", "child"),
+            ("child", "child", "grandchild"),
+            ("reference", "root", "linked"),
+            ("child", "stale", "root"),
+        ] {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        let roots = ["root".to_string()];
+        let mut unreachable = graph.find_unreachable(&roots, &["child".to_string()]);
+        unreachable.sort();
+        assert_eq!(unreachable, vec!["linked", "stale"]);
+        assert!(graph
+            .find_unreachable(&roots, &["child".to_string(), "reference".to_string()])
+            .contains(&"stale".to_string()));
+
+        let mut removed = graph.collect_unreachable(&roots, &["child".to_string()]);
+        removed.sort();
+        assert_eq!(removed, vec!["linked", "stale"]);
+        assert_eq!(graph.entities.len(), 3);
+        assert_eq!(graph.edges(&"reference".to_string()).count(), 0);
+    }
+
+    #[test]
+    fn test_incoming_neighbors() {
+        let mut graph = TestGraph::new();
+        for id in ["entity1", "entity2", "entity3"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from) in [("first", "entity1"), ("second", "entity2")] {
+            graph
+                .add_edge(
+                    relationship.to_string(),
+                    from.to_string(),
+                    "entity3".to_string(),
+                )
+                .unwrap();
+        }
+
+        let mut sources = graph
+            .get_incoming_neighbors(&"entity3".to_string())
+            .unwrap();
+        sources.sort();
+        assert_eq!(sources, vec!["entity1", "entity2"]);
+        assert_eq!(
+            graph
+                .get_incoming_neighbors_by_relationship(
+                    &"entity3".to_string(),
+                    &"second".to_string()
+                )
+                .unwrap(),
+            &vec!["entity2".to_string()]
+        );
+        assert!(graph
+            .get_incoming_neighbors(&"entity1".to_string())
+            .is_none());
+
+        graph
+            .remove_edge(
+                &"first".to_string(),
+                &"entity1".to_string(),
+                &"entity3".to_string(),
+            )
+            .unwrap();
+        graph.remove_entity(&"entity2".to_string());
+        assert!(graph
+            .get_incoming_neighbors(&"entity3".to_string())
+            .is_none());
+
+        graph
+            .add_edge(
+                "first".to_string(),
+                "entity1".to_string(),
+                "entity3".to_string(),
+            )
+            .unwrap();
+        let restored: TestGraph = serde_json::from_str(&graph.serialize().unwrap()).unwrap();
+        assert_eq!(
+            restored.get_incoming_neighbors(&"entity3".to_string()),
+            Some(vec![&"entity1".to_string()])
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_insertion_order_is_kept() {
+        let build = || {
+            let mut graph = TestGraph::new();
+            for id in ["c", "a", "d", "b"] {
+                graph.add_entity(id.to_string(), Components::new()).unwrap();
+            }
+            for (relationship, to) in [("z", "d"), ("y", "a"), ("x", "b")] {
+                graph
+                    .add_edge(relationship.to_string(), "c".to_string(), to.to_string())
+                    .unwrap();
+            }
+            graph
+        };
+        let mut graph = build();
+        assert_eq!(graph.serialize().unwrap(), build().serialize().unwrap());
+        assert_eq!(
+            graph.traverse_bfs("c".to_string()).unwrap(),
+            vec!["c", "d", "a", "b"]
+        );
+
+        graph.remove_entity(&"a".to_string());
+        assert_eq!(
+            graph.entities.keys().collect::<Vec<_>>(),
+            vec!["c", "d", "b"]
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_components_and_labels_keep_insertion_order() {
+        let mut graph = TestGraph::new();
+        let id = "psu".to_string();
+        graph
+            .add_entity(
+                id.clone(),
+                Components::from([
+                    ("volts".to_string(), Value::from(12)),
+                    ("amps".to_string(), Value::from(3)),
+                    ("name".to_string(), Value::from("Main")),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_component(&id, "color".to_string(), Value::from("black"))
+            .unwrap();
+        for label in ["power", "critical", "hardware"] {
+            graph.add_label(&id, label).unwrap();
+        }
+
+        let serialized = graph.serialize().unwrap();
+        let position = |needle: &str| serialized.find(needle).unwrap();
+        assert!(position("\"volts\"") < position("\"amps\""));
+        assert!(position("\"amps\"") < position("\"name\""));
+        assert!(position("\"name\"") < position("\"color\""));
+        assert!(position("\"power\"") < position("\"critical\""));
+        assert!(position("\"critical\"") < position("\"hardware\""));
+        assert_eq!(
+            serde_json::from_str::<TestGraph>(&serialized)
+                .unwrap()
+                .serialize()
+                .unwrap(),
+            serialized
+        );
+
+        graph.remove_component(&id, &"amps".to_string()).unwrap();
+        assert!(graph.remove_label(&id, "critical"));
+        assert_eq!(
+            graph.entities[&id].keys().collect::<Vec<_>>(),
+            vec!["volts", "name", "color"]
+        );
+        assert_eq!(
+            graph.labels[&id].iter().collect::<Vec<_>>(),
+            vec!["power", "hardware"]
+        );
+    }
+
+    #[test]
+    fn test_component_mutation() {
+        let mut graph = TestGraph::new();
+        let entity = "entity1".to_string();
+        let name = "name".to_string();
+        assert!(matches!(
+            graph.add_component(&entity, name.clone(), Value::from("first")),
+            Err(EntityGraphError::EntityNotFound { .. })
+        ));
+        assert!(graph
+            .upsert_entity(entity.clone(), Components::new())
+            .is_none());
+
+        graph
+            .add_component(&entity, name.clone(), Value::from("first"))
+            .unwrap();
+        assert!(matches!(
+            graph.add_component(&entity, name.clone(), Value::from("second")),
+            Err(EntityGraphError::ComponentAlreadyExists { .. })
+        ));
+        assert_eq!(
+            graph
+                .update_component(&entity, &name, Value::from("second"))
+                .unwrap(),
+            Value::from("first")
+        );
+        assert_eq!(
+            graph.remove_component(&entity, &name).unwrap(),
+            Value::from("second")
+        );
+        assert!(matches!(
+            graph.remove_component(&entity, &name),
+            Err(EntityGraphError::ComponentNotFound { .. })
+        ));
+        assert!(matches!(
+            graph.update_component(&entity, &name, Value::Null),
+            Err(EntityGraphError::ComponentNotFound { .. })
+        ));
+
+        let replaced = graph
+            .upsert_entity(
+                entity.clone(),
+                vec![(name.clone(), Value::from("third"))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+        assert!(replaced.is_empty());
+        assert_eq!(
+            graph.get_component(&entity, &name),
+            Some(&Value::from("third"))
+        );
+    }
+
+    #[test]
+    fn test_typed_components() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Settings {
+            volume: u8,
+            muted: bool,
+        }
+
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity("speaker".to_string(), Components::new())
+            .unwrap();
+        let speaker = "speaker".to_string();
+        let settings = "settings".to_string();
+        assert!(graph
+            .get_component_typed::<Settings>(&speaker, &settings)
+            .unwrap()
+            .is_none());
+
+        graph
+            .set_component_typed(
+                &speaker,
+                settings.clone(),
+                &Settings {
+                    volume: 7,
+                    muted: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            graph
+                .get_component_typed::<Settings>(&speaker, &settings)
+                .unwrap(),
+            Some(Settings {
+                volume: 7,
+                muted: false
+            })
+        );
+        assert!(matches!(
+            graph.get_component_typed::<String>(&speaker, &settings),
+            Err(EntityGraphError::DeserializationError(_))
+        ));
+        assert!(matches!(
+            graph.set_component_typed(&"missing".to_string(), settings, &1),
+            Err(EntityGraphError::EntityNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transform_components() {
+        let mut graph = TestGraph::new();
+        for (id, fahrenheit) in [("oven", 212.0), ("fridge", 32.0)] {
+            graph
+                .add_entity(
+                    id.to_string(),
+                    vec![("temperature".to_string(), Value::from(fahrenheit))]
+                        .into_iter()
+                        .collect(),
+                )
+                .unwrap();
+        }
+        graph
+            .add_entity("lamp".to_string(), Components::new())
+            .unwrap();
+        let checkpoint = graph.version();
+
+        let temperature = "temperature".to_string();
+        let mut changed = graph.transform_components(&temperature, |value| {
+            Value::from((value.as_f64().unwrap() - 32.0) * 5.0 / 9.0)
+        });
+        changed.sort();
+        assert_eq!(changed, vec!["fridge", "oven"]);
+        assert_eq!(
+            graph.get_component(&"oven".to_string(), &temperature),
+            Some(&Value::from(100.0))
+        );
+        assert_eq!(graph.export_changes_since(checkpoint).changed.len(), 2);
+
+        let checkpoint = graph.version();
+        assert!(graph
+            .transform_components(&temperature, |value| value.clone())
+            .is_empty());
+        assert_eq!(graph.version(), checkpoint);
+    }
+
+    #[test]
+    fn test_error_context() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity("A".to_string(), Components::new())
+            .unwrap();
+        let error = graph
+            .add_edge("child".to_string(), "A".to_string(), "B".to_string())
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            EntityGraphError::EdgeError {
+                endpoint: Endpoint::Target,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Cannot add 'child' edge: target entity \"B\" does not exist"
+        );
+
+        let error = graph
+            .remove_component(&"A".to_string(), &"mass".to_string())
+            .unwrap_err()
+            .context("loading scene");
+        assert_eq!(
+            error.to_string(),
+            "loading scene: Entity \"A\" has no 'mass' component"
+        );
+        assert!(matches!(
+            error.root_cause(),
+            EntityGraphError::ComponentNotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn test_traverse_path() {
+        let mut graph = TestGraph::new();
+        for id in ["machine", "io", "motion", "inputs", "outputs", "axes"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from, to) in [
+            ("has_module", "machine", "io"),
+            ("has_module", "machine", "motion"),
+            ("has_section", "io", "inputs"),
+            ("has_section", "io", "outputs"),
+            ("has_section", "motion", "axes"),
+            ("has_section", "machine", "io"),
+        ] {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        let levels = graph.traverse_path(
+            &"machine".to_string(),
+            &["has_module".to_string(), "has_section".to_string()],
+        );
+        assert_eq!(
+            levels,
+            vec![
+                vec!["io".to_string(), "motion".to_string()],
+                vec![
+                    "inputs".to_string(),
+                    "outputs".to_string(),
+                    "axes".to_string()
+                ],
+            ]
+        );
+
+        let levels = graph.traverse_path(
+            &"machine".to_string(),
+            &["has_section".to_string(), "has_module".to_string()],
+        );
+        assert_eq!(levels, vec![vec!["io".to_string()], vec![]]);
+    }
+
+    #[test]
+    fn test_edge_data() {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        let (road, a, b, c) = (
+            "road".to_string(),
+            "A".to_string(),
+            "B".to_string(),
+            "C".to_string(),
+        );
+        let weight = serde_json::json!({ "km": 12, "toll": true });
+        graph
+            .add_edge_with_data(road.clone(), a.clone(), b.clone(), weight.clone())
+            .unwrap();
+        graph.add_edge(road.clone(), b.clone(), c.clone()).unwrap();
+        assert_eq!(graph.get_edge_data(&road, &a, &b), Some(&weight));
+        assert_eq!(graph.get_edge_data(&road, &b, &c), None);
+        assert_eq!(graph.get_edge_data(&road, &b, &a), None);
+
+        assert_eq!(
+            graph.set_edge_data(&road, &b, &c, Value::from(3)).unwrap(),
+            None
+        );
+        assert!(matches!(
+            graph.set_edge_data(&road, &c, &a, Value::Null),
+            Err(EntityGraphError::EdgeNotFound { .. })
+        ));
+
+        let serialized = graph.serialize().unwrap();
+        let deserialized: TestGraph = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, graph);
+        assert_eq!(deserialized.get_edge_data(&road, &a, &b), Some(&weight));
+
+        graph.remove_edge(&road, &a, &b).unwrap();
+        assert_eq!(graph.get_edge_data(&road, &a, &b), None);
+        graph
+            .add_edge_with_data(road.clone(), a.clone(), b.clone(), weight)
+            .unwrap();
+        graph.remove_entity(&c);
+        assert_eq!(graph.get_edge_data(&road, &b, &c), None);
+        assert!(graph.get_edge_data(&road, &a, &b).is_some());
+    }
+
+    #[test]
+    fn test_duplicate_edge_policy() {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        let (a, b) = ("A".to_string(), "B".to_string());
+        let (link, unique, strict) = (
+            "link".to_string(),
+            "unique".to_string(),
+            "strict".to_string(),
+        );
+        graph.set_duplicate_edge_policy(unique.clone(), DuplicateEdgePolicy::DedupSilently);
+        graph.set_duplicate_edge_policy(strict.clone(), DuplicateEdgePolicy::ErrorOnDuplicate);
+        assert_eq!(
+            graph.duplicate_edge_policy(&link),
+            DuplicateEdgePolicy::AllowParallel
+        );
+
+        for relationship in [&link, &unique] {
+            for _ in 0..2 {
+                graph
+                    .add_edge(relationship.clone(), a.clone(), b.clone())
+                    .unwrap();
+            }
+        }
+        graph
+            .add_edge(strict.clone(), a.clone(), b.clone())
+            .unwrap();
+        assert!(matches!(
+            graph.add_edge(strict.clone(), a.clone(), b.clone()),
+            Err(EntityGraphError::DuplicateEdge { .. })
+        ));
+        graph
+            .add_edge(strict.clone(), b.clone(), a.clone())
+            .unwrap();
+
+        let count = |graph: &TestGraph, relationship| {
+            graph
+                .get_neighbors_by_relationship(&a, relationship)
+                .map_or(0, |neighbors| neighbors.len())
+        };
+        assert_eq!(count(&graph, &link), 2);
+        assert_eq!(count(&graph, &unique), 1);
+        assert_eq!(count(&graph, &strict), 1);
+        assert!(graph.contains_edge(&strict, &b, &a));
+        assert!(!graph.contains_edge(&unique, &b, &a));
+        assert!(!graph.contains_edge(&"missing".to_string(), &a, &b));
+
+        let deserialized: TestGraph = serde_json::from_str(&graph.serialize().unwrap()).unwrap();
+        assert_eq!(
+            deserialized.duplicate_edge_policy(&unique),
+            DuplicateEdgePolicy::DedupSilently
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C"] {
+            graph
+                .add_entity(
+                    id.to_string(),
+                    Components::from([("name".to_string(), Value::from(id))]),
+                )
+                .unwrap();
+        }
+        for (relationship, from, to) in [
+            ("child", "A", "B"),
+            ("child", "A", "C"),
+            ("child", "A", "C"),
+            ("power", "C", "A"),
+        ] {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 4);
+        let mut ids = graph.entity_ids().cloned().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["A", "B", "C"]);
+        assert!(graph
+            .entities()
+            .all(|(id, components)| components["name"] == id.as_str()));
+        assert_eq!(
+            graph.components(&"B".to_string()).unwrap()["name"],
+            Value::from("B")
+        );
+        assert!(graph.components(&"D".to_string()).is_none());
+
+        let mut relationships = graph.relationships().cloned().collect::<Vec<_>>();
+        relationships.sort();
+        assert_eq!(relationships, ["child", "power"]);
+        let mut edges = graph
+            .edges(&"child".to_string())
+            .map(|(from, to)| format!("{from}->{to}"))
+            .collect::<Vec<_>>();
+        edges.sort();
+        assert_eq!(edges, ["A->B", "A->C", "A->C"]);
+        assert_eq!(graph.edges(&"missing".to_string()).count(), 0);
     }
 
-    graphs
-}
-
-pub struct TypeRegistry {
-    deserialize_fn_map: HashMap<String, Box<dyn Fn(&Value) -> Result<Box<dyn Any + Send>, String>>>,
-    serialize_map: HashMap<String, Box<dyn Fn(&(dyn Any + Send)) -> Option<Value>>>,
-}
+    #[test]
+    fn test_batch_insertion() {
+        let mut graph: TestGraph = (0..3)
+            .map(|index| (format!("node{index}"), Components::new()))
+            .collect();
+        graph.extend([(
+            "node0".to_string(),
+            Components::from([("root".to_string(), Value::Bool(true))]),
+        )]);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(
+            graph.get_component(&"node0".to_string(), &"root".to_string()),
+            Some(&Value::Bool(true))
+        );
 
-impl TypeRegistry {
-    pub fn new() -> Self {
-        Self {
-            deserialize_fn_map: HashMap::new(),
-            serialize_map: HashMap::new(),
-        }
+        graph
+            .add_entities((3..5).map(|index| (format!("node{index}"), Components::new())))
+            .unwrap();
+        assert!(matches!(
+            graph.add_entities([
+                ("node5".to_string(), Components::new()),
+                ("node1".to_string(), Components::new()),
+            ]),
+            Err(EntityGraphError::EntityAlreadyExists { .. })
+        ));
+        assert_eq!(graph.node_count(), 6);
+
+        let next = "next".to_string();
+        graph
+            .add_edges(
+                next.clone(),
+                (1..6).map(|index| (format!("node{}", index - 1), format!("node{index}"))),
+            )
+            .unwrap();
+        assert_eq!(graph.edges(&next).count(), 5);
+        assert!(matches!(
+            graph.add_edges(next, [("node5".to_string(), "node9".to_string())]),
+            Err(EntityGraphError::EdgeError { .. })
+        ));
     }
 
-    // Register a type with its serialization function
+    #[test]
+    fn test_deserialize_with_id_mapper() {
+        let mut graph = TestGraph::new();
+        for id in ["node4", "node7"] {
+            graph
+                .add_entity(
+                    id.to_string(),
+                    Components::from([("mass".to_string(), Value::from(2))]),
+                )
+                .unwrap();
+        }
+        graph
+            .add_edge_with_data(
+                "link".to_string(),
+                "node4".to_string(),
+                "node7".to_string(),
+                Value::from("fast"),
+            )
+            .unwrap();
+        graph.add_label(&"node7".to_string(), "leaf").unwrap();
+        let serialized = graph.serialize().unwrap();
 
-    pub fn register<T: 'static + Send + Serialize + DeserializeOwned>(&mut self, type_name: &str) {
-        self.serialize_map.insert(
-            type_name.to_string(),
-            Box::new(move |any: &(dyn Any + Send)| {
-                any.downcast_ref::<T>()
-                    .and_then(|typed_ref| serde_json::to_value(typed_ref).ok())
-            }),
+        let mut registry = TypeRegistry::new();
+        register_types!(registry, (i32, "mass"));
+        let mut calls = 0;
+        let loaded = EntityGraph::<u64, String, String>::deserialize_with_id_mapper(
+            &serialized,
+            &registry,
+            |id: &String| {
+                calls += 1;
+                id.trim_start_matches("node").parse().unwrap()
+            },
+        )
+        .unwrap();
+        assert_eq!(calls, 2);
+        assert_eq!(
+            loaded.get_component(&4, &"mass".to_string()),
+            Some(&Value::from(2))
         );
-
-        self.deserialize_fn_map.insert(
-            type_name.to_string(),
-            Box::new(move |value: &Value| {
-                serde_json::from_value::<T>(value.clone())
-                    .map(|value| Box::new(value) as Box<dyn Any + Send>)
-                    .map_err(|e| e.to_string())
-            }),
+        assert_eq!(
+            loaded.get_edge_data(&"link".to_string(), &4, &7),
+            Some(&Value::from("fast"))
         );
+        assert!(loaded.has_label(&7, "leaf"));
+
+        assert!(matches!(
+            EntityGraph::<u64, String, String>::deserialize_with_id_mapper(
+                &serialized,
+                &registry,
+                |_: &String| 0
+            ),
+            Err(EntityGraphError::EntityAlreadyExists { .. })
+        ));
     }
 
-    pub fn deserialize_value(&self, type_name: &str, value: &Value) -> Result<Value, String> {
-        // Deserialize using the appropriate function from the map
-        if let Some(deserialize_fn) = self.deserialize_fn_map.get(type_name) {
-            let deserialized_value = deserialize_fn(value);
+    #[test]
+    fn test_with_capacity() {
+        let mut graph = TestGraph::with_capacity(100, 2);
+        assert!(graph.entities.capacity() >= 100);
+        assert!(graph.relationships.capacity() >= 2);
+        graph.reserve_entities(200);
+        assert!(graph.entities.capacity() >= 200);
+
+        for id in ["A", "B"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        let (link, a) = ("link".to_string(), "A".to_string());
+        graph.reserve_edges(&link, &a, 10);
+        assert_eq!(graph.edge_count(), 0);
+        graph
+            .add_edge(link.clone(), a.clone(), "B".to_string())
+            .unwrap();
+        graph.reserve_edges(&link, &a, 10);
+        assert!(graph.relationships[&link].edges[&a].capacity() >= 11);
+    }
 
-            // Attempt to re-serialize the deserialized value
-            if let Some(serialize_fn) = self.serialize_map.get(type_name) {
-                serialize_fn(&*deserialized_value?)
-                    .ok_or_else(|| format!("Failed to re-serialize for: {}", type_name))
-            } else {
-                Err(format!(
-                    "No serialization function found for type: {}",
-                    type_name
-                ))
+    #[test]
+    fn test_canonical_serialization() {
+        let build = |ids: &[&str]| {
+            let mut graph = TestGraph::new();
+            for id in ids {
+                let mut components = Components::new();
+                components.insert("name".to_string(), Value::from(*id));
+                components.insert("index".to_string(), Value::from(id.len()));
+                graph.add_entity(id.to_string(), components).unwrap();
+                graph.add_label(&id.to_string(), *id).unwrap();
+                graph.add_label(&id.to_string(), "node").unwrap();
             }
-        } else {
-            Err(format!(
-                "No deserialization function found for type: {}",
-                type_name
-            ))
-        }
+            for to in ids.iter().filter(|id| **id != "hub") {
+                graph
+                    .add_edge("links".to_string(), "hub".to_string(), to.to_string())
+                    .unwrap();
+            }
+            graph
+        };
+        let forward = build(&["hub", "a", "bb", "ccc", "dddd"]);
+        let backward = build(&["dddd", "ccc", "bb", "a", "hub"]);
+        let canonical = forward.serialize_canonical().unwrap();
+        assert_eq!(canonical, backward.serialize_canonical().unwrap());
+        assert!(canonical.contains("\n  \"entities\": {"));
+
+        let pretty = forward.serialize_pretty().unwrap();
+        let registry = {
+            let mut registry = TypeRegistry::new();
+            registry.register::<String>("name");
+            registry.register::<u64>("index");
+            registry
+        };
+        assert_eq!(
+            TestGraph::deserialize_with_registry(&pretty, &registry).unwrap(),
+            forward
+        );
     }
-}
 
-#[macro_export]
-macro_rules! register_types {
-    ($registry:expr, $(($t:ty, $s:expr)),* ) => {
-        $(
-            $registry.register::<$t>($s);
-        )*
-    };
-}
+    #[test]
+    fn test_streaming_serialization() {
+        let mut graph = TestGraph::new();
+        for index in 0..10 {
+            let mut components = Components::new();
+            components.insert("index".to_string(), Value::from(index));
+            graph.add_entity(format!("led{index}"), components).unwrap();
+        }
+        graph
+            .add_edge("next".to_string(), "led0".to_string(), "led1".to_string())
+            .unwrap();
+        let mut buffer = Vec::new();
+        graph.serialize_to_writer(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.clone()).unwrap(),
+            graph.serialize().unwrap()
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::Value;
+        let mut registry = TypeRegistry::new();
+        registry.register::<i64>("index");
+        let loaded = TestGraph::deserialize_from_reader(buffer.as_slice(), &registry).unwrap();
+        assert_eq!(loaded, graph);
+        assert!(
+            TestGraph::deserialize_from_reader(buffer.as_slice(), &TypeRegistry::new()).is_err()
+        );
+        assert!(
+            TestGraph::deserialize_from_reader(&buffer[..buffer.len() / 2], &registry).is_err()
+        );
 
-    type TestGraph = EntityGraph<String, String, String>;
+        let versioned = graph.serialize_versioned().unwrap();
+        let loaded = TestGraph::deserialize_from_reader(versioned.as_bytes(), &registry).unwrap();
+        assert_eq!(loaded, graph);
+        let checksummed = format!("  \n{}", graph.serialize_with_checksum().unwrap());
+        let loaded = TestGraph::deserialize_from_reader(checksummed.as_bytes(), &registry).unwrap();
+        assert_eq!(loaded, graph);
+        let tampered = checksummed.replace("led9", "led8");
+        assert!(matches!(
+            TestGraph::deserialize_from_reader(tampered.as_bytes(), &registry),
+            Err(EntityGraphError::IntegrityError(_))
+        ));
+        let newer = versioned.replace(
+            &format!("\"format_version\":{FORMAT_VERSION}"),
+            &format!("\"format_version\":{}", FORMAT_VERSION + 1),
+        );
+        assert!(TestGraph::deserialize_from_reader(newer.as_bytes(), &registry).is_err());
+    }
 
     #[test]
-    fn test_add_remove_entity() {
+    fn test_versioned_components() {
+        #[derive(Serialize, Deserialize)]
+        struct PositionV1 {
+            x: f32,
+        }
+        #[derive(Serialize, Deserialize)]
+        struct PositionV2 {
+            x: f32,
+            y: f32,
+        }
+        #[derive(Serialize, Deserialize)]
+        struct PositionV3 {
+            coordinates: [f32; 3],
+        }
+
+        let mut registry = TypeRegistry::new();
+        registry.register_versioned::<PositionV1, PositionV2>("position", |old| PositionV2 {
+            x: old.x,
+            y: 0.0,
+        });
+        registry.register_versioned::<PositionV2, PositionV3>("position", |old| PositionV3 {
+            coordinates: [old.x, old.y, 0.0],
+        });
+
         let mut graph = TestGraph::new();
-        assert!(graph
-            .add_entity(
-                "entity1".to_string(),
-                vec![
-                    ("component_name1".to_string(), Value::from("component1")),
-                    ("component_name2".to_string(), Value::from("component2"))
-                ]
-                .into_iter()
-                .collect()
-            )
-            .is_ok());
-        assert!(graph
-            .add_entity(
-                "entity1".to_string(),
-                vec![("component_name3".to_string(), Value::from("component3"))]
-                    .into_iter()
-                    .collect()
-            )
+        for (id, position) in [
+            ("v1", serde_json::json!({ "x": 1.0 })),
+            ("v2", serde_json::json!({ "x": 1.0, "y": 2.0 })),
+            ("v3", serde_json::json!({ "coordinates": [1.0, 2.0, 3.0] })),
+        ] {
+            let mut components = Components::new();
+            components.insert("position".to_string(), position);
+            graph.add_entity(id.to_string(), components).unwrap();
+        }
+        let loaded =
+            TestGraph::deserialize_with_registry(&graph.serialize().unwrap(), &registry).unwrap();
+        let position = |id: &str| {
+            loaded
+                .get_component(&id.to_string(), &"position".to_string())
+                .cloned()
+        };
+        assert_eq!(
+            position("v1"),
+            Some(serde_json::json!({ "coordinates": [1.0, 0.0, 0.0] }))
+        );
+        assert_eq!(
+            position("v2"),
+            Some(serde_json::json!({ "coordinates": [1.0, 2.0, 0.0] }))
+        );
+        assert_eq!(
+            position("v3"),
+            Some(serde_json::json!({ "coordinates": [1.0, 2.0, 3.0] }))
+        );
+        assert!(registry
+            .deserialize_value("position", &Value::from("north"))
             .is_err());
+    }
 
-        graph.remove_entity(&"entity1".to_string());
-        assert_eq!(graph.entities.contains_key(&"entity1".to_string()), false);
+    #[test]
+    fn test_registry_introspection() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<String>("name");
+        registry.register::<f64>("glow");
+        assert_eq!(registry.registered_types(), vec!["glow", "name"]);
+        assert!(registry.contains("glow"));
+
+        let mut plugin = TypeRegistry::new();
+        plugin.register::<u32>("glow");
+        plugin.register::<bool>("visible");
+        registry.merge(plugin);
+        assert_eq!(registry.registered_types(), vec!["glow", "name", "visible"]);
+        assert!(registry
+            .deserialize_value("glow", &Value::from(0.5))
+            .is_err());
+
+        assert!(registry.unregister("visible"));
+        assert!(!registry.unregister("visible"));
+        assert!(!registry.contains("visible"));
+        assert!(registry
+            .deserialize_value("visible", &Value::from(true))
+            .is_err());
     }
 
     #[test]
-    fn test_add_edge() {
+    fn test_unknown_component_policy() {
+        let mut graph = TestGraph::new();
+        let mut components = Components::new();
+        components.insert("name".to_string(), Value::from("led"));
+        components.insert("glow".to_string(), Value::from(0.5));
+        graph.add_entity("led1".to_string(), components).unwrap();
+        let serialized = graph.serialize().unwrap();
+        let mut registry = TypeRegistry::new();
+        registry.register::<String>("name");
+
+        assert!(TestGraph::deserialize_with_unknown_components(
+            &serialized,
+            &registry,
+            UnknownComponentPolicy::Error
+        )
+        .is_err());
+        let unknown = vec![("led1".to_string(), "glow".to_string())];
+        let (skipped, skipped_unknown) = TestGraph::deserialize_with_unknown_components(
+            &serialized,
+            &registry,
+            UnknownComponentPolicy::SkipUnknown,
+        )
+        .unwrap();
+        assert_eq!(skipped_unknown, unknown);
+        assert!(skipped
+            .get_component(&"led1".to_string(), &"glow".to_string())
+            .is_none());
+        assert!(skipped
+            .get_component(&"led1".to_string(), &"name".to_string())
+            .is_some());
+        let (kept, kept_unknown) = TestGraph::deserialize_with_unknown_components(
+            &serialized,
+            &registry,
+            UnknownComponentPolicy::PreserveRaw,
+        )
+        .unwrap();
+        assert_eq!(kept_unknown, unknown);
+        assert_eq!(kept, graph);
+
+        registry.set_unknown_component_policy(UnknownComponentPolicy::SkipUnknown);
+        let loaded = TestGraph::deserialize_with_registry(&serialized, &registry).unwrap();
+        assert_eq!(loaded, skipped);
+        registry.set_unknown_component_policy(UnknownComponentPolicy::PreserveRaw);
+        let loaded = TestGraph::deserialize_with_registry(&serialized, &registry).unwrap();
+        assert_eq!(loaded, graph);
+    }
+
+    #[test]
+    fn test_remove_edge() {
         let mut graph = TestGraph::new();
+        for id in ["entity1", "entity2", "entity3"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        let relationship = "relationship".to_string();
+        for to in ["entity2", "entity3"] {
+            graph
+                .add_edge(relationship.clone(), "entity1".to_string(), to.to_string())
+                .unwrap();
+        }
         graph
-            .add_entity(
-                "entity1".to_string(),
-                vec![("component_name1".to_string(), Value::from("component1"))]
-                    .into_iter()
-                    .collect(),
+            .add_edge(
+                "other".to_string(),
+                "entity2".to_string(),
+                "entity3".to_string(),
             )
             .unwrap();
+
         graph
-            .add_entity(
-                "entity2".to_string(),
-                vec![("component_name2".to_string(), Value::from("component2"))]
-                    .into_iter()
-                    .collect(),
+            .remove_edge(
+                &relationship,
+                &"entity1".to_string(),
+                &"entity2".to_string(),
             )
             .unwrap();
+        assert_eq!(
+            graph.relationships[&relationship].edges["entity1"],
+            vec!["entity3".to_string()]
+        );
+        assert!(matches!(
+            graph.remove_edge(
+                &relationship,
+                &"entity1".to_string(),
+                &"entity2".to_string()
+            ),
+            Err(EntityGraphError::EdgeNotFound { .. })
+        ));
 
-        assert!(graph
-            .add_edge(
-                "relationship".to_string(),
-                "entity1".to_string(),
-                "entity2".to_string()
-            )
-            .is_ok());
-        assert!(graph
-            .add_edge(
-                "relationship".to_string(),
-                "entity1".to_string(),
-                "entity3".to_string()
+        graph
+            .remove_edge(
+                &relationship,
+                &"entity1".to_string(),
+                &"entity3".to_string(),
             )
-            .is_err());
+            .unwrap();
+        assert!(!graph.relationships.contains_key(&relationship));
+
+        graph.remove_relationship(&"other".to_string()).unwrap();
+        assert!(graph.relationships.is_empty());
+        assert!(matches!(
+            graph.remove_relationship(&"other".to_string()),
+            Err(EntityGraphError::RelationshipNotFound { .. })
+        ));
     }
 
     #[cfg(feature = "petgraph")]
@@ -567,10 +2997,18 @@ mod tests {
         let mut graph = TestGraph::new();
 
         // Adding entities
-        graph.add_entity("A".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("B".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("C".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("D".to_string(), HashMap::new()).unwrap();
+        graph
+            .add_entity("A".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("B".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("C".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("D".to_string(), Components::new())
+            .unwrap();
 
         // Adding edges
         graph
@@ -599,10 +3037,18 @@ mod tests {
         let mut graph = TestGraph::new();
 
         // Adding entities
-        graph.add_entity("A".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("B".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("C".to_string(), HashMap::new()).unwrap();
-        graph.add_entity("D".to_string(), HashMap::new()).unwrap();
+        graph
+            .add_entity("A".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("B".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("C".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("D".to_string(), Components::new())
+            .unwrap();
 
         // Adding edges
         graph
@@ -626,6 +3072,157 @@ mod tests {
         assert_eq!(traversal_result, expected_traversal);
     }
 
+    #[test]
+    fn test_lazy_traversal() {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C", "D"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (from, to) in [("A", "B"), ("B", "C"), ("C", "D"), ("D", "A")] {
+            graph
+                .add_edge("next".to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        assert_eq!(
+            graph.iter_dfs("A".to_string()).take(2).collect::<Vec<_>>(),
+            vec!["A", "B"]
+        );
+        assert_eq!(
+            graph.iter_bfs("B".to_string()).position(|id| id == "A"),
+            Some(3)
+        );
+        assert_eq!(
+            graph.iter_bfs("A".to_string()).collect::<Vec<_>>(),
+            graph.traverse_bfs("A".to_string()).unwrap()
+        );
+        assert_eq!(
+            graph.iter_dfs("unknown".to_string()).collect::<Vec<_>>(),
+            vec!["unknown"]
+        );
+    }
+
+    #[test]
+    fn test_limited_traversal() {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C", "D", "E"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from, to) in [
+            ("child", "A", "B"),
+            ("child", "B", "C"),
+            ("child", "C", "D"),
+            ("link", "A", "E"),
+        ] {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        let children = ["child".to_string()];
+        assert_eq!(
+            graph.traverse_bfs_limited("A".to_string(), Some(&children), Some(2)),
+            vec![
+                ("A".to_string(), 0),
+                ("B".to_string(), 1),
+                ("C".to_string(), 2)
+            ]
+        );
+        assert_eq!(
+            graph.traverse_dfs_limited("A".to_string(), Some(&children), None),
+            vec![
+                ("A".to_string(), 0),
+                ("B".to_string(), 1),
+                ("C".to_string(), 2),
+                ("D".to_string(), 3)
+            ]
+        );
+
+        let mut everything = graph.traverse_bfs_limited("A".to_string(), None, Some(1));
+        everything.sort();
+        assert_eq!(
+            everything,
+            vec![
+                ("A".to_string(), 0),
+                ("B".to_string(), 1),
+                ("E".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            graph.traverse_dfs_limited("A".to_string(), None, Some(0)),
+            vec![("A".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_find_entities_paged() {
+        let mut graph = TestGraph::new();
+        for index in 0..10 {
+            graph
+                .add_entity(
+                    format!("entity{index}"),
+                    vec![("index".to_string(), Value::from(index))]
+                        .into_iter()
+                        .collect(),
+                )
+                .unwrap();
+        }
+        graph
+            .add_entity("empty".to_string(), Components::new())
+            .unwrap();
+
+        let has_index =
+            |_: &String, components: &Components<String>| components.contains_key("index");
+        assert_eq!(graph.find_entities(has_index).len(), 10);
+
+        let page = graph.find_entities_paged(has_index, |a, b| b.cmp(a), 2, 3);
+        assert_eq!(page.total_matches, 10);
+        assert_eq!(page.items, vec!["entity7", "entity6", "entity5"]);
+
+        let last = graph.find_entities_paged(has_index, |a, b| a.cmp(b), 8, 5);
+        assert_eq!(last.items, vec!["entity8", "entity9"]);
+
+        let past_end = graph.find_entities_paged(has_index, |a, b| a.cmp(b), 20, 5);
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.total_matches, 10);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity("A".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("B".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_entity("C".to_string(), Components::new())
+            .unwrap();
+
+        assert!(graph.add_label(&"A".to_string(), "device").unwrap());
+        assert!(!graph.add_label(&"A".to_string(), "device").unwrap());
+        graph.add_label(&"B".to_string(), "device").unwrap();
+        graph.add_label(&"B".to_string(), "led").unwrap();
+        assert!(graph.add_label(&"missing".to_string(), "device").is_err());
+
+        let mut devices = graph.entities_with_label("device").collect::<Vec<_>>();
+        devices.sort();
+        assert_eq!(devices, vec!["A", "B"]);
+        assert!(graph.has_label(&"B".to_string(), "led"));
+
+        let restored =
+            TestGraph::deserialize_with_registry(&graph.serialize().unwrap(), &TypeRegistry::new())
+                .unwrap();
+        assert_eq!(restored, graph);
+        assert_eq!(restored.entities_with_label("led").count(), 1);
+
+        assert!(graph.remove_label(&"B".to_string(), "device"));
+        graph.remove_entity(&"A".to_string());
+        assert_eq!(graph.entities_with_label("device").count(), 0);
+        assert!(graph.labels(&"A".to_string()).is_none());
+    }
+
     #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
     enum ComponentKey {
         Position,
@@ -686,7 +3283,9 @@ mod tests {
                     .collect(),
             )
             .unwrap();
-        graph.add_entity("C".to_string(), HashMap::new()).unwrap();
+        graph
+            .add_entity("C".to_string(), Components::new())
+            .unwrap();
 
         // Add edges for traversal
         graph
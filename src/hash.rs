@@ -0,0 +1,227 @@
+use crate::{
+    serde::{serialize_ecs, serialize_with_entities},
+    Description, EntityGraph,
+};
+use legion::{Entity, World};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::{fmt::Display, hash::Hash};
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is fixed across Rust versions and
+/// platforms, so hashes can be persisted.
+pub(crate) struct ContentHasher(u64);
+
+impl ContentHasher {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Writes a length-prefixed string, so that adjacent fields can't run into each other.
+    fn write_str(&mut self, value: &str) {
+        self.write(&(value.len() as u64).to_le_bytes());
+        self.write(value.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Canonical JSON: `serde_json::Value` keeps object keys sorted.
+fn canonical_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_value(value)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+fn hash_records(hasher: &mut ContentHasher, tag: &str, mut records: Vec<String>) {
+    records.sort();
+    hasher.write_str(tag);
+    hasher.write(&(records.len() as u64).to_le_bytes());
+    for record in &records {
+        hasher.write_str(record);
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// A hash of the graph's entities, components, labels and edges that doesn't depend on
+    /// insertion order, map iteration order or the platform. Graphs that compare equal have the
+    /// same hash; the order of an entity's neighbors is not part of the hash.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        let entities = self
+            .entities
+            .iter()
+            .map(|(id, components)| canonical_json(&(id, components)))
+            .collect();
+        hash_records(&mut hasher, "entities", entities);
+
+        let labels = self
+            .labels
+            .iter()
+            .flat_map(|(id, labels)| labels.iter().map(move |label| canonical_json(&(id, label))))
+            .collect();
+        hash_records(&mut hasher, "labels", labels);
+
+        let edges = self
+            .relationships
+            .iter()
+            .flat_map(|(relationship, adjacency_list)| {
+                adjacency_list
+                    .edges
+                    .iter()
+                    .flat_map(move |(from, neighbors)| {
+                        neighbors
+                            .iter()
+                            .map(move |to| match adjacency_list.edge_data(from, to) {
+                                Some(data) => canonical_json(&(relationship, from, to, data)),
+                                None => canonical_json(&(relationship, from, to)),
+                            })
+                    })
+            })
+            .collect();
+        hash_records(&mut hasher, "edges", edges);
+        hasher.finish()
+    }
+}
+
+struct SerializableWorld<'a>(&'a World);
+
+impl Serialize for SerializableWorld<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ecs(self.0, serializer)
+    }
+}
+
+struct SerializableEntity(Entity);
+
+impl Serialize for SerializableEntity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_with_entities(&self.0, serializer)
+    }
+}
+
+impl Description {
+    /// Every node name with the JSON object of its registered components, keyed by their
+    /// registered names, or `Null` if the node has none.
+    pub(crate) fn registered_components(&self) -> Vec<(&str, Value)> {
+        let world = serde_json::to_value(SerializableWorld(&self.data)).unwrap_or_default();
+        let entities = world.get("entities");
+        self.node_name_to_entity
+            .iter()
+            .map(|(name, entity)| {
+                let components = serde_json::to_value(SerializableEntity(*entity))
+                    .ok()
+                    .and_then(|id| Some(entities?.get(id.as_str()?)?.clone()))
+                    .unwrap_or(Value::Null);
+                (name.as_str(), components)
+            })
+            .collect()
+    }
+
+    /// A hash of the description's nodes, registered component values and edges that doesn't
+    /// depend on the underlying entity IDs, so two descriptions built the same way hash the same.
+    /// Components whose types aren't registered are not part of the hash.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        let nodes = self
+            .registered_components()
+            .iter()
+            .map(|(name, components)| canonical_json(&(name, components)))
+            .collect();
+        hash_records(&mut hasher, "nodes", nodes);
+
+        let edges = self
+            .graphs
+            .iter()
+            .flat_map(|(edge_name, graph)| {
+                graph.edge_references().map(move |edge| {
+                    let from = self.name_of(graph[edge.source()]);
+                    let to = self.name_of(graph[edge.target()]);
+                    canonical_json(&(edge_name, from, to, edge.weight()))
+                })
+            })
+            .collect();
+        hash_records(&mut hasher, "edges", edges);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Components;
+    use crate::{register_component, DescriptionBuilder, EntityGraph};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn entity_graph_hash_ignores_insertion_order() {
+        let mut first = TestGraph::new();
+        let mut second = TestGraph::new();
+        for id in ["A", "B", "C"] {
+            first.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for id in ["C", "B", "A"] {
+            second
+                .add_entity(id.to_string(), Components::new())
+                .unwrap();
+        }
+        for graph in [&mut first, &mut second] {
+            graph
+                .add_edge("link".to_string(), "A".to_string(), "B".to_string())
+                .unwrap();
+        }
+        assert_eq!(first.content_hash(), second.content_hash());
+
+        second
+            .add_entity(
+                "D".to_string(),
+                vec![("value".to_string(), Value::from(1))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+        assert_ne!(first.content_hash(), second.content_hash());
+
+        second.remove_entity(&"D".to_string());
+        assert_eq!(first.content_hash(), second.content_hash());
+        second.add_label(&"A".to_string(), "root").unwrap();
+        assert_ne!(first.content_hash(), second.content_hash());
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Position(i32);
+
+    #[test]
+    fn description_hash_ignores_entity_ids() {
+        register_component::<Position>("hash_position").unwrap();
+        let build = |value| {
+            let mut builder = DescriptionBuilder::new();
+            builder
+                .add_node("node1".to_string(), (Position(value),))
+                .unwrap();
+            builder
+                .add_node("node2".to_string(), (Position(2),))
+                .unwrap();
+            builder.add_edge("link", "node1", vec!["node2"]).unwrap();
+            builder.build()
+        };
+        assert_eq!(build(1).content_hash(), build(1).content_hash());
+        assert_ne!(build(1).content_hash(), build(3).content_hash());
+    }
+}
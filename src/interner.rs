@@ -0,0 +1,125 @@
+use serde_json::Value;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+/// Hash-consed component values. Equal values intern to the same `Arc`, so a value repeated
+/// many times is stored once and equality is a pointer comparison.
+///
+/// `EntityGraph` keeps its components as plain `Value`s; the interner is for callers that hold
+/// their own copies of repeated values.
+#[derive(Debug, Default, Clone)]
+pub struct ValueInterner {
+    // Buckets keyed by a structural hash of the value, as `Value` doesn't implement `Hash`.
+    values: HashMap<u64, Vec<Arc<Value>>>,
+    len: usize,
+}
+
+impl ValueInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `value`, added if no equal value has been interned yet.
+    pub fn intern(&mut self, value: Value) -> Arc<Value> {
+        let bucket = self.values.entry(value_hash(&value)).or_default();
+        if let Some(shared) = bucket.iter().find(|shared| ***shared == value) {
+            return shared.clone();
+        }
+        let shared = Arc::new(value);
+        bucket.push(shared.clone());
+        self.len += 1;
+        shared
+    }
+
+    /// The number of distinct values interned.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops values no longer referenced outside the interner.
+    pub fn shrink(&mut self) {
+        self.values.retain(|_, bucket| {
+            bucket.retain(|value| Arc::strong_count(value) > 1);
+            !bucket.is_empty()
+        });
+        self.len = self.values.values().map(Vec::len).sum();
+    }
+}
+
+fn value_hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_value(value, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_value(value: &Value, state: &mut impl Hasher) {
+    std::mem::discriminant(value).hash(state);
+    match value {
+        Value::Null => {}
+        Value::Bool(value) => value.hash(state),
+        Value::Number(number) => number.hash(state),
+        Value::String(string) => string.hash(state),
+        Value::Array(values) => {
+            values.len().hash(state);
+            for value in values {
+                hash_value(value, state);
+            }
+        }
+        Value::Object(map) => {
+            // Entries are combined without regard to order, since objects compare as maps.
+            let combined = map.iter().fold(0u64, |combined, (key, value)| {
+                let mut entry = DefaultHasher::new();
+                key.hash(&mut entry);
+                hash_value(value, &mut entry);
+                combined.wrapping_add(entry.finish())
+            });
+            map.len().hash(state);
+            combined.hash(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn identical_values_are_shared() {
+        let mut interner = ValueInterner::new();
+        let shared = (0..100)
+            .map(|index| {
+                interner.intern(json!({ "color": { "r": 1, "g": 0 }, "index": index % 10 }))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(interner.len(), 10);
+        assert!(Arc::ptr_eq(&shared[3], &shared[93]));
+        assert!(!Arc::ptr_eq(&shared[3], &shared[4]));
+
+        let reordered = interner.intern(json!({ "index": 3, "color": { "g": 0, "r": 1 } }));
+        assert!(Arc::ptr_eq(&reordered, &shared[3]));
+        assert!(!Arc::ptr_eq(
+            &interner.intern(Value::from(1)),
+            &interner.intern(Value::from(1.0))
+        ));
+        assert!(!Arc::ptr_eq(
+            &interner.intern(Value::from("1")),
+            &interner.intern(Value::from(1))
+        ));
+        assert_eq!(interner.len(), 13);
+
+        drop(shared);
+        let kept = interner.intern(Value::from("kept"));
+        interner.intern(Value::from("dropped"));
+        interner.shrink();
+        assert_eq!(interner.len(), 2);
+        assert!(Arc::ptr_eq(&kept, &interner.intern(Value::from("kept"))));
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// A single change made to a `Description` after it was built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mutation {
+    NodeAdded {
+        name: String,
+    },
+    NodeRemoved {
+        name: String,
+    },
+    ComponentChanged {
+        node: String,
+        component: String,
+    },
+    EdgeAdded {
+        edge_name: String,
+        from: String,
+        to: String,
+    },
+    EdgeRemoved {
+        edge_name: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Mutation {
+    /// The same mutation with every node name in it passed through `rename`.
+    pub(crate) fn rename_nodes(self, rename: impl Fn(&str) -> String) -> Self {
+        match self {
+            Self::NodeAdded { name } => Self::NodeAdded {
+                name: rename(&name),
+            },
+            Self::NodeRemoved { name } => Self::NodeRemoved {
+                name: rename(&name),
+            },
+            Self::ComponentChanged { node, component } => Self::ComponentChanged {
+                node: rename(&node),
+                component,
+            },
+            Self::EdgeAdded {
+                edge_name,
+                from,
+                to,
+            } => Self::EdgeAdded {
+                edge_name,
+                from: rename(&from),
+                to: rename(&to),
+            },
+            Self::EdgeRemoved {
+                edge_name,
+                from,
+                to,
+            } => Self::EdgeRemoved {
+                edge_name,
+                from: rename(&from),
+                to: rename(&to),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: SystemTime,
+    pub mutation: Mutation,
+}
+
+/// An append-only log of mutations, in the order they were applied.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a mutation stamped with the current time.
+    pub fn record(&mut self, mutation: Mutation) {
+        self.entries.push(JournalEntry {
+            timestamp: SystemTime::now(),
+            mutation,
+        });
+    }
+
+    /// Appends the entries of `other` with their timestamps, passing the node names in them
+    /// through `rename`.
+    pub(crate) fn append(&mut self, other: Journal, rename: impl Fn(&str) -> String) {
+        self.entries
+            .extend(other.entries.into_iter().map(|entry| JournalEntry {
+                timestamp: entry.timestamp,
+                mutation: entry.mutation.rename_nodes(&rename),
+            }));
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, JournalEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Journal {
+    type Item = &'a JournalEntry;
+    type IntoIter = std::slice::Iter<'a, JournalEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_records_in_order() {
+        let mut journal = Journal::new();
+        journal.record(Mutation::NodeAdded {
+            name: "node1".to_string(),
+        });
+        journal.record(Mutation::NodeRemoved {
+            name: "node1".to_string(),
+        });
+
+        let mutations = journal
+            .iter()
+            .map(|entry| entry.mutation.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mutations,
+            vec![
+                Mutation::NodeAdded {
+                    name: "node1".to_string()
+                },
+                Mutation::NodeRemoved {
+                    name: "node1".to_string()
+                },
+            ]
+        );
+
+        let json = serde_json::to_string(&journal).unwrap();
+        let restored: Journal = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, journal);
+    }
+}
@@ -0,0 +1,186 @@
+//! JSON Schemas for registered component types, generated with `schemars`, and checking stored
+//! values against them with `jsonschema`.
+
+use crate::{graph::id_string, EntityGraph, TypeRegistry};
+use jsonschema::Validator;
+use schemars::{schema_for, JsonSchema};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+/// A component value that doesn't match the schema registered for its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaMismatch<ID, K> {
+    pub id: ID,
+    pub component: K,
+    /// A JSON Pointer to the part of the value that doesn't match, empty for the whole value.
+    pub path: String,
+    pub message: String,
+}
+
+impl<ID: Serialize, K: Display> Display for SchemaMismatch<ID, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Component '{}' of entity {} at '{}': {}",
+            self.component,
+            id_string(&self.id),
+            self.path,
+            self.message
+        )
+    }
+}
+
+/// The schema registered for a component type, with the validator compiled from it.
+pub(crate) struct ComponentSchema {
+    schema: Value,
+    validator: Validator,
+}
+
+impl TypeRegistry {
+    /// Like `register`, also recording the JSON Schema `schemars` generates for `T`, which the
+    /// type's values must match.
+    pub fn register_with_schema<T>(&mut self, type_name: &str)
+    where
+        T: 'static + Send + Serialize + DeserializeOwned + JsonSchema,
+    {
+        self.register::<T>(type_name);
+        let schema = serde_json::to_value(schema_for!(T)).expect("schemas serialize to JSON");
+        let validator = jsonschema::validator_for(&schema).expect("schemars emits valid schemas");
+        self.schemas
+            .insert(type_name.to_string(), ComponentSchema { schema, validator });
+    }
+
+    pub fn json_schema(&self, type_name: &str) -> Option<&Value> {
+        self.schemas.get(type_name).map(|schema| &schema.schema)
+    }
+
+    /// A JSON Schema for a graph's components object, with a property for every registered
+    /// type: its schema if it was registered with one, or one accepting any value.
+    pub fn json_schemas(&self) -> Value {
+        let properties = self
+            .registered_types()
+            .into_iter()
+            .map(|name| {
+                let schema = self.json_schema(name).cloned().unwrap_or(json!({}));
+                (name.to_string(), schema)
+            })
+            .collect::<Map<_, _>>();
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false,
+        })
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Every way a component value doesn't match the schema registered for its key with
+    /// `register_with_schema`. Components without a schema aren't checked.
+    pub fn validate_against_schemas(&self, registry: &TypeRegistry) -> Vec<SchemaMismatch<ID, K>> {
+        let mut mismatches = Vec::new();
+        for (id, components) in &self.entities {
+            for (component, value) in components {
+                let Some(schema) = registry.schemas.get(&component.to_string()) else {
+                    continue;
+                };
+                mismatches.extend(schema.validator.iter_errors(value).map(|error| {
+                    SchemaMismatch {
+                        id: id.clone(),
+                        component: component.clone(),
+                        path: error.instance_path().as_str().to_string(),
+                        message: error.to_string(),
+                    }
+                }));
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Position {
+        #[schemars(range(min = 0))]
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(rename_all = "lowercase")]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[test]
+    fn components_are_validated_against_schemas() {
+        let mut registry = TypeRegistry::new();
+        registry.register_with_schema::<Position>("position");
+        registry.register_with_schema::<Color>("color");
+        registry.register::<u32>("count");
+
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "good".to_string(),
+                Components::from([
+                    ("position".to_string(), json!({ "x": 1, "y": 2.5 })),
+                    ("color".to_string(), json!("red")),
+                    ("count".to_string(), json!("not checked")),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "bad".to_string(),
+                Components::from([
+                    ("position".to_string(), json!({ "x": -1, "z": 0 })),
+                    ("color".to_string(), json!("blue")),
+                ]),
+            )
+            .unwrap();
+
+        let mut mismatches = graph
+            .validate_against_schemas(&registry)
+            .into_iter()
+            .map(|mismatch| {
+                assert_eq!(mismatch.id, "bad");
+                (mismatch.component, mismatch.path)
+            })
+            .collect::<Vec<_>>();
+        mismatches.sort();
+        mismatches.dedup();
+        assert_eq!(
+            mismatches,
+            vec![
+                ("color".to_string(), "".to_string()),
+                ("position".to_string(), "".to_string()),
+                ("position".to_string(), "/x".to_string()),
+            ]
+        );
+
+        let schemas = registry.json_schemas();
+        assert_eq!(schemas["properties"]["count"], json!({}));
+        assert_eq!(
+            schemas["properties"]["color"]["enum"],
+            json!(["red", "green"])
+        );
+    }
+}
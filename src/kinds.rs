@@ -0,0 +1,229 @@
+use crate::{graph::id_string, Components, EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+/// What an entity of one kind must have and may do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityKind<K, R> {
+    /// Components every entity of the kind must have.
+    pub components: Vec<K>,
+    /// Relationships the kind's entities may have outgoing edges of, or `None` for any.
+    pub relationships: Option<Vec<R>>,
+}
+
+/// Entity kinds by name, such as `"device"` or `"led_module"`. An entity added with
+/// `EntityGraph::add_entity_of_kind` is labelled with its kind, so its edges can be checked by
+/// `EntityGraph::add_edge_of_kind`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindRegistry<K, R> {
+    kinds: HashMap<String, EntityKind<K, R>>,
+}
+
+impl<K, R> Default for KindRegistry<K, R> {
+    fn default() -> Self {
+        Self {
+            kinds: HashMap::new(),
+        }
+    }
+}
+
+impl<K, R> KindRegistry<K, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a kind whose entities need `components` and may have edges of any
+    /// relationship. Replaces any kind with the same name.
+    pub fn register(
+        &mut self,
+        kind: impl Into<String>,
+        components: impl IntoIterator<Item = K>,
+    ) -> &mut Self {
+        self.kinds.insert(
+            kind.into(),
+            EntityKind {
+                components: components.into_iter().collect(),
+                relationships: None,
+            },
+        );
+        self
+    }
+
+    /// Registers a kind whose entities need `components` and may only have outgoing edges of
+    /// `relationships`.
+    pub fn register_with_relationships(
+        &mut self,
+        kind: impl Into<String>,
+        components: impl IntoIterator<Item = K>,
+        relationships: impl IntoIterator<Item = R>,
+    ) -> &mut Self {
+        self.kinds.insert(
+            kind.into(),
+            EntityKind {
+                components: components.into_iter().collect(),
+                relationships: Some(relationships.into_iter().collect()),
+            },
+        );
+        self
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&EntityKind<K, R>> {
+        self.kinds.get(kind)
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = &str> + '_ {
+        self.kinds.keys().map(String::as_str)
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Adds an entity of a registered kind, labelled with the kind's name. Fails with
+    /// `UnknownKind` for an unregistered kind and `ComponentNotFound` if a component the kind
+    /// requires is missing.
+    pub fn add_entity_of_kind(
+        &mut self,
+        registry: &KindRegistry<K, R>,
+        kind: &str,
+        id: ID,
+        components: Components<K>,
+    ) -> Result<(), EntityGraphError> {
+        let entity_kind = registry
+            .get(kind)
+            .ok_or_else(|| EntityGraphError::UnknownKind {
+                kind: kind.to_string(),
+            })?;
+        if let Some(missing) = entity_kind
+            .components
+            .iter()
+            .find(|key| !components.contains_key(*key))
+        {
+            return Err(EntityGraphError::component_not_found(&id, missing));
+        }
+        self.add_entity(id.clone(), components)?;
+        self.add_label(&id, kind)?;
+        Ok(())
+    }
+
+    /// The registered kind `id` was labelled with, if any.
+    pub fn kind_of<'a>(&self, registry: &'a KindRegistry<K, R>, id: &ID) -> Option<&'a str> {
+        let labels = self.labels.get(id)?;
+        registry.kinds().find(|kind| labels.contains(*kind))
+    }
+
+    /// Adds an edge like `add_edge`, failing with `RelationshipNotAllowed` if the kind of
+    /// `from` doesn't allow the relationship. Edges from entities without a kind are not
+    /// checked.
+    pub fn add_edge_of_kind(
+        &mut self,
+        registry: &KindRegistry<K, R>,
+        relationship_key: R,
+        from: ID,
+        to: ID,
+    ) -> Result<(), EntityGraphError> {
+        if let Some(kind) = self.kind_of(registry, &from) {
+            let allowed = registry
+                .get(kind)
+                .and_then(|kind| kind.relationships.as_ref());
+            if allowed.is_some_and(|allowed| !allowed.contains(&relationship_key)) {
+                return Err(EntityGraphError::RelationshipNotAllowed {
+                    relationship: relationship_key.to_string(),
+                    kind: kind.to_string(),
+                    id: id_string(&from),
+                });
+            }
+        }
+        self.add_edge(relationship_key, from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn kinds_are_validated() {
+        let mut registry = KindRegistry::new();
+        registry
+            .register_with_relationships(
+                "device",
+                ["serial".to_string()],
+                ["has_module".to_string()],
+            )
+            .register("led_module", ["color".to_string()]);
+
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity_of_kind(
+                &registry,
+                "device",
+                "device1".to_string(),
+                Components::from([("serial".to_string(), Value::from("A-1"))]),
+            )
+            .unwrap();
+        graph
+            .add_entity_of_kind(
+                &registry,
+                "led_module",
+                "led1".to_string(),
+                Components::from([("color".to_string(), Value::from("red"))]),
+            )
+            .unwrap();
+        assert!(matches!(
+            graph.add_entity_of_kind(
+                &registry,
+                "led_module",
+                "led2".to_string(),
+                Components::new()
+            ),
+            Err(EntityGraphError::ComponentNotFound { .. })
+        ));
+        assert!(matches!(
+            graph.add_entity_of_kind(
+                &registry,
+                "sensor",
+                "sensor1".to_string(),
+                Components::new()
+            ),
+            Err(EntityGraphError::UnknownKind { .. })
+        ));
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(
+            graph.kind_of(&registry, &"device1".to_string()),
+            Some("device")
+        );
+
+        graph
+            .add_edge_of_kind(
+                &registry,
+                "has_module".to_string(),
+                "device1".to_string(),
+                "led1".to_string(),
+            )
+            .unwrap();
+        assert!(matches!(
+            graph.add_edge_of_kind(
+                &registry,
+                "powers".to_string(),
+                "device1".to_string(),
+                "led1".to_string(),
+            ),
+            Err(EntityGraphError::RelationshipNotAllowed { .. })
+        ));
+        graph
+            .add_edge_of_kind(
+                &registry,
+                "powers".to_string(),
+                "led1".to_string(),
+                "device1".to_string(),
+            )
+            .unwrap();
+    }
+}
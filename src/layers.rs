@@ -0,0 +1,190 @@
+use crate::{Components, EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    hash::Hash,
+    io::Write,
+};
+
+/// The entities at one depth from the root of `EntityGraph::layers`, with the edges that can
+/// be drawn once they are loaded.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(bound(
+    serialize = "ID: Serialize, K: Serialize",
+    deserialize = "ID: Deserialize<'de>, K: Deserialize<'de>"
+))]
+pub struct Layer<ID, K: Eq + Hash> {
+    pub depth: usize,
+    pub entities: Vec<(ID, Components<K>)>,
+    /// Edges between entities of this layer and earlier ones.
+    pub edges: Vec<LayerEdge<ID>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct LayerEdge<ID> {
+    pub from: ID,
+    pub to: ID,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// The entities reachable from `root` over `relationship`, grouped by their breadth-first
+    /// depth. Each edge of the relationship between them is in the layer of its deeper
+    /// endpoint. Returns no layers if `root` isn't an entity.
+    pub fn layers(&self, root: &ID, relationship: &R) -> Vec<Layer<ID, K>> {
+        if !self.entities.contains_key(root) {
+            return Vec::new();
+        }
+        let mut depths = HashMap::from([(root, 0)]);
+        let mut order = vec![root];
+        let mut queue = VecDeque::from([root]);
+        while let Some(id) = queue.pop_front() {
+            let depth = depths[id];
+            for neighbor in self
+                .get_neighbors_by_relationship(id, relationship)
+                .into_iter()
+                .flatten()
+            {
+                if !depths.contains_key(neighbor) && self.entities.contains_key(neighbor) {
+                    depths.insert(neighbor, depth + 1);
+                    order.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let mut layers = Vec::<Layer<ID, K>>::new();
+        for id in &order {
+            let depth = depths[id];
+            if layers.len() == depth {
+                layers.push(Layer {
+                    depth,
+                    entities: Vec::new(),
+                    edges: Vec::new(),
+                });
+            }
+            layers[depth]
+                .entities
+                .push(((*id).clone(), self.entities[*id].clone()));
+        }
+        for from in &order {
+            for to in self
+                .get_neighbors_by_relationship(from, relationship)
+                .into_iter()
+                .flatten()
+            {
+                let Some(to_depth) = depths.get(to) else {
+                    continue;
+                };
+                layers[depths[from].max(*to_depth)].edges.push(LayerEdge {
+                    from: (*from).clone(),
+                    to: to.clone(),
+                    data: self.get_edge_data(relationship, from, to).cloned(),
+                });
+            }
+        }
+        layers
+    }
+
+    /// Writes `layers(root, relationship)` as JSON lines, one layer per line and shallowest
+    /// first, so a reader can show the first hops before the rest has arrived.
+    pub fn serialize_layers(
+        &self,
+        root: &ID,
+        relationship: &R,
+        mut writer: impl Write,
+    ) -> Result<(), EntityGraphError> {
+        for layer in self.layers(root, relationship) {
+            serde_json::to_writer(&mut writer, &layer)
+                .map_err(|error| EntityGraphError::SerializationError(error.to_string()))?;
+            writeln!(writer)
+                .map_err(|error| EntityGraphError::SerializationError(error.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the entities and `relationship` edges of a layer, as read back from
+    /// `serialize_layers`. Layers must be loaded in order.
+    pub fn load_layer(
+        &mut self,
+        relationship: R,
+        layer: Layer<ID, K>,
+    ) -> Result<(), EntityGraphError> {
+        self.add_entities(layer.entities)?;
+        for edge in layer.edges {
+            self.add_edge_with_optional_data(relationship.clone(), edge.from, edge.to, edge.data)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn layers_stream_in_depth_order() {
+        let mut graph = TestGraph::new();
+        for id in ["plant", "line1", "line2", "press", "robot", "office"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        let contains = "contains".to_string();
+        for (from, to) in [
+            ("plant", "line1"),
+            ("plant", "line2"),
+            ("line1", "press"),
+            ("line2", "press"),
+            ("line2", "robot"),
+        ] {
+            graph
+                .add_edge(contains.clone(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+
+        let layers = graph.layers(&"plant".to_string(), &contains);
+        let ids = |layer: &Layer<String, String>| {
+            let mut ids = layer
+                .entities
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids.join(",")
+        };
+        assert_eq!(
+            layers.iter().map(ids).collect::<Vec<_>>(),
+            ["plant", "line1,line2", "press,robot"]
+        );
+        assert_eq!(
+            layers
+                .iter()
+                .map(|layer| layer.edges.len())
+                .collect::<Vec<_>>(),
+            [0, 2, 3]
+        );
+        assert!(graph.layers(&"missing".to_string(), &contains).is_empty());
+
+        let mut buffer = Vec::new();
+        graph
+            .serialize_layers(&"plant".to_string(), &contains, &mut buffer)
+            .unwrap();
+        let mut loaded = TestGraph::new();
+        for line in buffer.lines() {
+            let layer = serde_json::from_str(&line.unwrap()).unwrap();
+            loaded.load_layer(contains.clone(), layer).unwrap();
+        }
+        graph.remove_entity(&"office".to_string());
+        assert_eq!(loaded, graph);
+    }
+}
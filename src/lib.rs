@@ -1,11 +1,78 @@
+mod algorithms;
 mod anymap;
+mod archetype;
+#[cfg(feature = "binary")]
+mod binary;
+mod changes;
+#[cfg(feature = "codegen")]
+mod codegen;
+#[cfg(feature = "compression")]
+mod compression;
+mod declarative;
 mod description;
+mod dot;
+mod envelope;
+mod formats;
+mod frozen;
+pub mod generators;
 mod graph;
+mod hash;
+mod interner;
+mod journal;
+#[cfg(feature = "json-schema")]
+mod json_schema;
+mod kinds;
+mod layers;
+mod merge;
+mod mermaid;
+mod metadata;
+mod namespace;
+mod partition;
+mod prefab;
+mod query;
+mod redact;
+mod remote;
+mod sampling;
+mod schema;
 mod serde;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+pub mod testing;
+mod traversal;
+mod typed;
 
 pub use self::{
+    algorithms::WeightedEdge,
     anymap::AnyMap,
-    description::{Description, DescriptionBuilder, Error},
+    changes::{ChangeSet, EntityChange},
+    declarative::DeclarativeDescription,
+    description::{
+        DefaultComponents, Description, DescriptionBuilder, DescriptionStats, DuplicateEdgePolicy,
+        EdgeWeight, Error, MergePolicy, NameConflict, Provenance,
+    },
+    envelope::{FormatMigrations, FORMAT_VERSION},
+    frozen::FrozenGraph,
     graph::*,
-    serde::{deserialize_ecs, register_component, serialize_ecs},
+    interner::ValueInterner,
+    journal::{Journal, JournalEntry, Mutation},
+    kinds::{EntityKind, KindRegistry},
+    layers::{Layer, LayerEdge},
+    merge::{ComponentResolver, MergeStrategy},
+    metadata::Metadata,
+    partition::{CutEdge, Shard},
+    prefab::{Prefab, PrefabRegistry},
+    query::Query,
+    redact::FilteredView,
+    remote::{GraphStore, InMemoryStore, RemoteEdge, RemoteGraph, RemoteNode},
+    sampling::SampleStrategy,
+    schema::{GraphSchema, RelationshipRule, ValueShape, Violation},
+    serde::{
+        deserialize_ecs, register_cloneable_component, register_component, serialize_ecs,
+        ComponentRegistry,
+    },
+    traversal::{Direction, NodeFilter, TraversalOptions},
+    typed::TypedComponents,
 };
+
+#[cfg(feature = "json-schema")]
+pub use self::json_schema::SchemaMismatch;
@@ -0,0 +1,249 @@
+use crate::EntityGraph;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fmt::Display, hash::Hash};
+
+/// Resolves a component conflict from the entity ID, component key, the value already present
+/// and the incoming value.
+pub type ComponentResolver<'a, ID, K> = Box<dyn FnMut(&ID, &K, &Value, &Value) -> Value + 'a>;
+
+/// How `EntityGraph::merge` resolves a component that both graphs give the same entity.
+pub enum MergeStrategy<'a, ID, K> {
+    /// Keep the value already in the graph being merged into.
+    KeepSelf,
+    /// Take the value from the graph being merged in.
+    KeepOther,
+    /// Call `resolve(id, key, self_value, other_value)` and keep what it returns.
+    Custom(ComponentResolver<'a, ID, K>),
+}
+
+impl<ID, K> MergeStrategy<'_, ID, K> {
+    fn resolve(&mut self, id: &ID, key: &K, current: &Value, incoming: Value) -> Value {
+        match self {
+            MergeStrategy::KeepSelf => current.clone(),
+            MergeStrategy::KeepOther => incoming,
+            MergeStrategy::Custom(resolve) => resolve(id, key, current, &incoming),
+        }
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Adds the entities, components, labels and edges of `other`. An entity in both graphs
+    /// ends up with the components of both, using `strategy` for keys they share. Edges already
+    /// in this graph are not added again; their payload is replaced only by `KeepOther`, or
+    /// taken from `other` if they have none.
+    pub fn merge(&mut self, other: Self, mut strategy: MergeStrategy<'_, ID, K>) {
+        let keep_other = matches!(strategy, MergeStrategy::KeepOther);
+        self.merge_with(
+            other,
+            |id, key, current, incoming| strategy.resolve(id, key, current, incoming.clone()),
+            |_, _, _, current, incoming| {
+                if keep_other {
+                    incoming.clone()
+                } else {
+                    current.clone()
+                }
+            },
+        );
+    }
+
+    /// Like `merge`, with conflicts resolved by callbacks. `resolve_component(id, key, current,
+    /// incoming)` is called for each component both graphs give an entity with different
+    /// values, and `resolve_edge(relationship, from, to, current, incoming)` for each edge both
+    /// graphs have with different payloads. Each keeps what its callback returns.
+    pub fn merge_with<C, E>(&mut self, other: Self, mut resolve_component: C, mut resolve_edge: E)
+    where
+        C: FnMut(&ID, &K, &Value, &Value) -> Value,
+        E: FnMut(&R, &ID, &ID, &Value, &Value) -> Value,
+    {
+        for (id, components) in other.entities {
+            let Some(existing) = self.entities.get_mut(&id) else {
+                self.add_entity(id, components).unwrap();
+                continue;
+            };
+            let mut changed = false;
+            for (key, incoming) in components {
+                let merged = match existing.get(&key) {
+                    Some(current) if *current != incoming => {
+                        resolve_component(&id, &key, current, &incoming)
+                    }
+                    _ => incoming,
+                };
+                if existing.get(&key) != Some(&merged) {
+                    existing.insert(key, merged);
+                    changed = true;
+                }
+            }
+            if changed {
+                self.changes.touch(&id);
+            }
+        }
+
+        for (id, labels) in other.labels {
+            for label in labels {
+                self.add_label(&id, label).unwrap();
+            }
+        }
+
+        for (relationship, adjacency_list) in other.relationships {
+            for (from, neighbors) in &adjacency_list.edges {
+                for to in neighbors {
+                    let data = adjacency_list.edge_data(from, to).cloned();
+                    if !self.contains_edge(&relationship, from, to) {
+                        self.add_edge_with_optional_data(
+                            relationship.clone(),
+                            from.clone(),
+                            to.clone(),
+                            data,
+                        )
+                        .unwrap();
+                        continue;
+                    }
+                    let Some(incoming) = data else {
+                        continue;
+                    };
+                    let merged = match self.get_edge_data(&relationship, from, to) {
+                        Some(current) if *current == incoming => continue,
+                        Some(current) => resolve_edge(&relationship, from, to, current, &incoming),
+                        None => incoming,
+                    };
+                    if self.get_edge_data(&relationship, from, to) != Some(&merged) {
+                        self.set_edge_data(&relationship, from, to, merged).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn device(id: &str, version: i64) -> TestGraph {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "bus".to_string(),
+                Components::from([("version".to_string(), Value::from(version))]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                id.to_string(),
+                Components::from([("version".to_string(), Value::from(version))]),
+            )
+            .unwrap();
+        graph
+            .add_edge("attached".to_string(), id.to_string(), "bus".to_string())
+            .unwrap();
+        graph.add_label(&"bus".to_string(), id).unwrap();
+        graph
+    }
+
+    #[test]
+    fn merge_strategies() {
+        let bus = "bus".to_string();
+        let version = "version".to_string();
+
+        let mut graph = device("left", 1);
+        graph.merge(device("right", 2), MergeStrategy::KeepSelf);
+        assert_eq!(graph.entities.len(), 3);
+        assert_eq!(graph.get_component(&bus, &version), Some(&Value::from(1)));
+        assert_eq!(graph.get_incoming_neighbors(&bus).unwrap().len(), 2);
+        assert_eq!(graph.labels(&bus).unwrap().len(), 2);
+
+        let mut graph = device("left", 1);
+        graph.merge(device("left", 2), MergeStrategy::KeepOther);
+        assert_eq!(graph.get_component(&bus, &version), Some(&Value::from(2)));
+        assert_eq!(graph.get_incoming_neighbors(&bus).unwrap().len(), 1);
+
+        let mut graph = device("left", 1);
+        graph.merge(
+            device("right", 2),
+            MergeStrategy::Custom(Box::new(|_, _, current, incoming| {
+                Value::from(current.as_i64().unwrap().max(incoming.as_i64().unwrap()) + 10)
+            })),
+        );
+        assert_eq!(graph.get_component(&bus, &version), Some(&Value::from(12)));
+        assert_eq!(
+            graph.get_component(&"left".to_string(), &version),
+            Some(&Value::from(1))
+        );
+    }
+
+    #[test]
+    fn merge_with_callbacks() {
+        let weighted = |version, weight: i64| {
+            let mut graph = device("left", version);
+            graph
+                .set_edge_data(
+                    &"attached".to_string(),
+                    &"left".to_string(),
+                    &"bus".to_string(),
+                    Value::from(weight),
+                )
+                .unwrap();
+            graph
+        };
+
+        let mut graph = weighted(1, 5);
+        let mut component_conflicts = Vec::new();
+        graph.merge_with(
+            weighted(2, 3),
+            |id, key, current, incoming| {
+                component_conflicts.push(format!("{id}.{key}"));
+                Value::from(current.as_i64().unwrap() + incoming.as_i64().unwrap())
+            },
+            |relationship, from, to, current, incoming| {
+                assert_eq!(
+                    (relationship.as_str(), from.as_str(), to.as_str()),
+                    ("attached", "left", "bus")
+                );
+                Value::from(current.as_i64().unwrap().min(incoming.as_i64().unwrap()))
+            },
+        );
+        component_conflicts.sort();
+        assert_eq!(component_conflicts, ["bus.version", "left.version"]);
+        assert_eq!(
+            graph.get_component(&"bus".to_string(), &"version".to_string()),
+            Some(&Value::from(3))
+        );
+        assert_eq!(
+            graph.get_edge_data(
+                &"attached".to_string(),
+                &"left".to_string(),
+                &"bus".to_string()
+            ),
+            Some(&Value::from(3))
+        );
+
+        let mut graph = weighted(1, 5);
+        graph.merge(weighted(1, 3), MergeStrategy::KeepSelf);
+        assert_eq!(
+            graph.get_edge_data(
+                &"attached".to_string(),
+                &"left".to_string(),
+                &"bus".to_string()
+            ),
+            Some(&Value::from(5))
+        );
+        graph.merge(weighted(1, 3), MergeStrategy::KeepOther);
+        assert_eq!(
+            graph.get_edge_data(
+                &"attached".to_string(),
+                &"left".to_string(),
+                &"bus".to_string()
+            ),
+            Some(&Value::from(3))
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{formats::node_name, Description, EntityGraph};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt::Display, fmt::Write, hash::Hash};
+
+pub(crate) type DiagramParts = (
+    BTreeMap<String, BTreeMap<String, Value>>,
+    Vec<(String, String, String)>,
+);
+
+// Longer component values are cut short in summaries.
+const MAX_VALUE_LENGTH: usize = 24;
+
+/// Escapes text for a quoted Mermaid label.
+fn escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+/// A component value as short text for a diagram label.
+pub(crate) fn summarize(value: &Value) -> String {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+    if text.chars().count() <= MAX_VALUE_LENGTH {
+        return text;
+    }
+    let mut short = text.chars().take(MAX_VALUE_LENGTH - 1).collect::<String>();
+    short.push('…');
+    short
+}
+
+/// Writes a flowchart of nodes sorted by name and edges labelled with their relationship.
+fn flowchart(
+    nodes: &BTreeMap<String, BTreeMap<String, Value>>,
+    mut edges: Vec<(String, String, String)>,
+    with_components: bool,
+) -> String {
+    let mut names = nodes.keys().cloned().collect::<Vec<_>>();
+    for (_, from, to) in &edges {
+        names.extend([from.clone(), to.clone()]);
+    }
+    names.sort();
+    names.dedup();
+    let index = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.as_str(), index))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut out = String::from("flowchart LR\n");
+    for (position, name) in names.iter().enumerate() {
+        let mut label = escape(name);
+        if with_components {
+            for (key, value) in nodes.get(name).into_iter().flatten() {
+                let _ = write!(label, "<br/>{}: {}", escape(key), escape(&summarize(value)));
+            }
+        }
+        let _ = writeln!(out, "    n{}[\"{}\"]", position, label);
+    }
+    edges.sort();
+    for (relationship, from, to) in &edges {
+        let _ = writeln!(
+            out,
+            "    n{} -->|\"{}\"| n{}",
+            index[from.as_str()],
+            escape(relationship),
+            index[to.as_str()]
+        );
+    }
+    out
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// A Mermaid flowchart of the graph, with edges labelled by relationship.
+    pub fn to_mermaid(&self) -> String {
+        self.mermaid(false)
+    }
+
+    /// Like `to_mermaid`, listing each entity's components under its name.
+    pub fn to_mermaid_with_components(&self) -> String {
+        self.mermaid(true)
+    }
+
+    fn mermaid(&self, with_components: bool) -> String {
+        let nodes = self
+            .entities
+            .iter()
+            .map(|(id, components)| {
+                let components = components
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect();
+                (node_name(id), components)
+            })
+            .collect();
+        let edges = self
+            .relationships
+            .iter()
+            .flat_map(|(relationship, adjacency_list)| {
+                adjacency_list
+                    .edges
+                    .iter()
+                    .flat_map(move |(from, neighbors)| {
+                        neighbors.iter().map(move |to| {
+                            (relationship.to_string(), node_name(from), node_name(to))
+                        })
+                    })
+            })
+            .collect();
+        flowchart(&nodes, edges, with_components)
+    }
+}
+
+impl Description {
+    /// A Mermaid flowchart of the description, with edges labelled by edge name.
+    pub fn to_mermaid(&self) -> String {
+        self.mermaid(false)
+    }
+
+    /// Like `to_mermaid`, listing each node's registered components under its name.
+    pub fn to_mermaid_with_components(&self) -> String {
+        self.mermaid(true)
+    }
+
+    fn mermaid(&self, with_components: bool) -> String {
+        let (nodes, edges) = self.diagram_parts();
+        flowchart(&nodes, edges, with_components)
+    }
+
+    /// Each node's registered components by node name, and every edge as
+    /// `(edge_name, from, to)`.
+    pub(crate) fn diagram_parts(&self) -> DiagramParts {
+        let document = self.to_declarative();
+        let edges = document
+            .edges
+            .into_iter()
+            .flat_map(|(edge_name, sources)| {
+                sources.into_iter().flat_map(move |(from, targets)| {
+                    let edge_name = edge_name.clone();
+                    targets
+                        .into_iter()
+                        .map(move |to| (edge_name.clone(), from.clone(), to))
+                })
+            })
+            .collect();
+        (document.nodes, edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use crate::{DescriptionBuilder, Error};
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn mermaid_flowchart() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "psu".to_string(),
+                Components::from([
+                    ("volts".to_string(), Value::from(12)),
+                    (
+                        "model".to_string(),
+                        Value::from("a \"very\" long model name indeed"),
+                    ),
+                ]),
+            )
+            .unwrap();
+        graph
+            .add_entity("led".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge("powers".to_string(), "psu".to_string(), "led".to_string())
+            .unwrap();
+
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart LR\n    n0[\"led\"]\n    n1[\"psu\"]\n    n1 -->|\"powers\"| n0\n"
+        );
+        assert_eq!(
+            graph.to_mermaid_with_components(),
+            "flowchart LR\n    n0[\"led\"]\n    n1[\"psu<br/>model: a #quot;very#quot; long model nam…<br/>volts: 12\"]\n    n1 -->|\"powers\"| n0\n"
+        );
+    }
+
+    #[test]
+    fn description_mermaid_flowchart() -> Result<(), Error> {
+        let mut builder = DescriptionBuilder::new();
+        builder.add_node("frame".to_string(), ())?;
+        builder.add_node("wheel".to_string(), ())?;
+        builder.add_edge("holds", "frame", vec!["wheel"])?;
+        assert_eq!(
+            builder.build().to_mermaid(),
+            "flowchart LR\n    n0[\"frame\"]\n    n1[\"wheel\"]\n    n0 -->|\"holds\"| n1\n"
+        );
+        Ok(())
+    }
+}
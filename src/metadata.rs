@@ -0,0 +1,158 @@
+use crate::{Description, EntityGraph};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, hash::Hash, time::SystemTime};
+
+/// Information about a saved graph as a whole, serialized along with it.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<SystemTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<SystemTime>,
+    /// The version of the application that wrote the graph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application_version: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl Metadata {
+    /// Metadata created and last modified now.
+    pub fn new() -> Self {
+        let now = SystemTime::now();
+        Self {
+            created: Some(now),
+            modified: Some(now),
+            ..Self::default()
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn application_version(mut self, version: impl Into<String>) -> Self {
+        self.application_version = Some(version.into());
+        self
+    }
+
+    pub fn property(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// Fills in the fields `other` has and this metadata doesn't, or replaces them if
+    /// `overwrite` is set, and likewise for each property. Keeps the earlier creation time and
+    /// the later modification time.
+    pub(crate) fn merge(&mut self, other: Metadata, overwrite: bool) {
+        let take = |field: &mut Option<String>, value: Option<String>| {
+            if overwrite || field.is_none() {
+                *field = value.or(field.take());
+            }
+        };
+        take(&mut self.title, other.title);
+        take(&mut self.author, other.author);
+        take(&mut self.application_version, other.application_version);
+        self.created = self.created.into_iter().chain(other.created).min();
+        self.modified = self.modified.into_iter().chain(other.modified).max();
+        for (key, value) in other.properties {
+            if overwrite || !self.properties.contains_key(&key) {
+                self.properties.insert(key, value);
+            }
+        }
+    }
+
+    /// Sets the modified time to now. Graphs don't do this themselves, so call it before saving.
+    pub fn touch(&mut self) {
+        self.modified = Some(SystemTime::now());
+    }
+}
+
+impl<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> EntityGraph<ID, K, R> {
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn metadata_mut(&mut self) -> Option<&mut Metadata> {
+        self.metadata.as_mut()
+    }
+
+    /// Replaces the graph's metadata, returning the previous metadata.
+    pub fn set_metadata(&mut self, metadata: Metadata) -> Option<Metadata> {
+        self.metadata.replace(metadata)
+    }
+}
+
+impl Description {
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn metadata_mut(&mut self) -> Option<&mut Metadata> {
+        self.metadata.as_mut()
+    }
+
+    /// Replaces the description's metadata, returning the previous metadata.
+    pub fn set_metadata(&mut self, metadata: Metadata) -> Option<Metadata> {
+        self.metadata.replace(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DescriptionBuilder;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn metadata_serializes_with_the_graph() {
+        let mut graph = TestGraph::new();
+        assert!(serde_json::to_value(&graph)
+            .unwrap()
+            .get("metadata")
+            .is_none());
+
+        let metadata = Metadata::new()
+            .title("Plant floor")
+            .author("controls")
+            .application_version("1.4.0")
+            .property("site", "north");
+        graph.set_metadata(metadata.clone());
+        let created = metadata.created;
+        graph.metadata_mut().unwrap().touch();
+        let restored: TestGraph = serde_json::from_str(&graph.serialize().unwrap()).unwrap();
+        let restored = restored.metadata().unwrap();
+        assert_eq!(restored.title.as_deref(), Some("Plant floor"));
+        assert_eq!(restored.created, created);
+        assert!(restored.modified >= created);
+        assert_eq!(restored.properties["site"], "north");
+
+        let mut builder = DescriptionBuilder::new();
+        builder.metadata(Metadata::default().title("Line 1"));
+        let description = builder.build();
+        let restored: Description =
+            serde_json::from_str(&serde_json::to_string(&description).unwrap()).unwrap();
+        assert_eq!(restored.metadata(), description.metadata());
+        assert_eq!(
+            description
+                .to_declarative()
+                .metadata
+                .unwrap()
+                .title
+                .as_deref(),
+            Some("Line 1")
+        );
+    }
+}
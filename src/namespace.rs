@@ -0,0 +1,145 @@
+use crate::{
+    graph::{reverse_relationships, Map},
+    AdjacencyList, EntityGraph, EntityGraphError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+};
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Renames every entity to `remap(id)`, updating its edges, edges pointing at it, and its
+    /// labels. Fails without changing anything if two entities would get the same ID.
+    pub fn remap_ids<F>(&mut self, mut remap: F) -> Result<(), EntityGraphError>
+    where
+        F: FnMut(&ID) -> ID,
+    {
+        let mut mapping = HashMap::with_capacity(self.entities.len());
+        let mut new_ids = HashSet::with_capacity(self.entities.len());
+        for id in self.entities.keys() {
+            let new_id = remap(id);
+            if !new_ids.insert(new_id.clone()) {
+                return Err(EntityGraphError::entity_already_exists(&new_id));
+            }
+            mapping.insert(id.clone(), new_id);
+        }
+        let map = |id: &ID| mapping.get(id).cloned().unwrap_or_else(|| id.clone());
+
+        for id in self.entities.keys() {
+            self.changes.remove(id);
+        }
+        self.entities = std::mem::take(&mut self.entities)
+            .into_iter()
+            .map(|(id, components)| (map(&id), components))
+            .collect();
+        self.relationships = std::mem::take(&mut self.relationships)
+            .into_iter()
+            .map(|(relationship, adjacency_list)| {
+                let edges = adjacency_list
+                    .edges
+                    .into_iter()
+                    .map(|(from, neighbors)| (map(&from), neighbors.iter().map(map).collect()))
+                    .collect::<Map<_, _>>();
+                let data = adjacency_list
+                    .data
+                    .into_iter()
+                    .map(|(from, targets)| {
+                        let targets = targets
+                            .into_iter()
+                            .map(|(to, data)| (map(&to), data))
+                            .collect::<Map<_, _>>();
+                        (map(&from), targets)
+                    })
+                    .collect::<Map<_, _>>();
+                (relationship, AdjacencyList { edges, data })
+            })
+            .collect();
+        self.labels = std::mem::take(&mut self.labels)
+            .into_iter()
+            .map(|(id, labels)| (map(&id), labels))
+            .collect();
+        for ids in self.label_index.values_mut() {
+            *ids = ids.iter().map(map).collect();
+        }
+        self.incoming = reverse_relationships(&self.relationships);
+        for id in mapping.values() {
+            self.changes.touch(id);
+        }
+        Ok(())
+    }
+}
+
+impl<K, R> EntityGraph<String, K, R>
+where
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Prepends `prefix` to every entity ID, e.g. before merging graphs whose IDs overlap.
+    pub fn prefix_ids(&mut self, prefix: &str) -> Result<(), EntityGraphError> {
+        self.remap_ids(|id| format!("{prefix}{id}"))
+    }
+
+    /// Removes `prefix` from every entity ID that starts with it. Other IDs are left alone.
+    pub fn strip_id_prefix(&mut self, prefix: &str) -> Result<(), EntityGraphError> {
+        self.remap_ids(|id| id.strip_prefix(prefix).unwrap_or(id).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn sample() -> TestGraph {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        graph
+            .add_edge("link".to_string(), "A".to_string(), "B".to_string())
+            .unwrap();
+        graph.add_label(&"B".to_string(), "leaf").unwrap();
+        graph
+    }
+
+    #[test]
+    fn prefix_and_strip_ids() {
+        let mut graph = sample();
+        graph.prefix_ids("left/").unwrap();
+        assert!(graph.entities.contains_key("left/A"));
+        assert_eq!(
+            graph.get_neighbors(&"left/A".to_string()).unwrap(),
+            vec!["left/B"]
+        );
+        assert_eq!(
+            graph.get_incoming_neighbors(&"left/B".to_string()).unwrap(),
+            vec!["left/A"]
+        );
+        assert_eq!(
+            graph.entities_with_label("leaf").collect::<Vec<_>>(),
+            vec!["left/B"]
+        );
+
+        graph.strip_id_prefix("left/").unwrap();
+        assert_eq!(graph, sample());
+    }
+
+    #[test]
+    fn remap_rejects_collisions() {
+        let mut graph = sample();
+        assert!(matches!(
+            graph.remap_ids(|_| "same".to_string()),
+            Err(EntityGraphError::EntityAlreadyExists { .. })
+        ));
+        assert_eq!(graph, sample());
+    }
+}
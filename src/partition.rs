@@ -0,0 +1,216 @@
+use crate::{EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+/// An edge whose endpoints were assigned to different shards.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CutEdge<ID, R> {
+    pub relationship: R,
+    pub from: ID,
+    pub to: ID,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// One piece of a partitioned graph. `graph` holds the shard's entities and the edges between
+/// them; edges leaving the shard are kept in `cut_edges`, owned by the shard of their source.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(bound(
+    serialize = "EntityGraph<ID, K, R>: Serialize, ID: Serialize, R: Serialize",
+    deserialize = "EntityGraph<ID, K, R>: Deserialize<'de>, ID: Deserialize<'de>, R: Deserialize<'de>"
+))]
+pub struct Shard<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    pub graph: EntityGraph<ID, K, R>,
+    pub cut_edges: Vec<CutEdge<ID, R>>,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Splits the graph into shards keyed by `shard_key`. Entities keep their components and
+    /// labels; edges crossing shards are recorded as cut edges.
+    pub fn partition_by<S, F>(&self, mut shard_key: F) -> HashMap<S, Shard<ID, K, R>>
+    where
+        S: Eq + Hash + Clone,
+        F: FnMut(&ID) -> S,
+    {
+        let mut shards = HashMap::new();
+        let mut assignments = HashMap::new();
+        for (id, components) in &self.entities {
+            let key = shard_key(id);
+            assignments.insert(id.clone(), key.clone());
+            let shard = shards.entry(key).or_insert_with(|| Shard {
+                graph: EntityGraph::new(),
+                cut_edges: Vec::new(),
+            });
+            shard.graph.entities.insert(id.clone(), components.clone());
+            if let Some(labels) = self.labels.get(id) {
+                for label in labels {
+                    shard.graph.add_label(id, label.clone()).unwrap();
+                }
+            }
+        }
+
+        for (relationship, adjacency_list) in &self.relationships {
+            for (from, neighbors) in &adjacency_list.edges {
+                let Some(from_shard) = assignments.get(from) else {
+                    continue;
+                };
+                let shard = shards.get_mut(from_shard).unwrap();
+                for to in neighbors {
+                    let data = adjacency_list.edge_data(from, to).cloned();
+                    if assignments.get(to) == Some(from_shard) {
+                        shard
+                            .graph
+                            .add_edge_with_optional_data(
+                                relationship.clone(),
+                                from.clone(),
+                                to.clone(),
+                                data,
+                            )
+                            .unwrap();
+                    } else {
+                        shard.cut_edges.push(CutEdge {
+                            relationship: relationship.clone(),
+                            from: from.clone(),
+                            to: to.clone(),
+                            data,
+                        });
+                    }
+                }
+            }
+        }
+        shards
+    }
+
+    /// Splits the graph into `count` shards by hashing entity IDs. `count` is clamped to at
+    /// least one. Shards may be empty.
+    pub fn partition(&self, count: usize) -> Vec<Shard<ID, K, R>> {
+        let count = count.max(1);
+        let mut shards = self.partition_by(|id| {
+            let mut hasher = DefaultHasher::new();
+            id.hash(&mut hasher);
+            (hasher.finish() % count as u64) as usize
+        });
+        (0..count)
+            .map(|index| {
+                shards.remove(&index).unwrap_or_else(|| Shard {
+                    graph: EntityGraph::new(),
+                    cut_edges: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Rebuilds a single graph from shards produced by `partition` or `partition_by`.
+    /// An entity's edges are restored with its in-shard edges ahead of its cut edges.
+    pub fn reassemble(
+        shards: impl IntoIterator<Item = Shard<ID, K, R>>,
+    ) -> Result<Self, EntityGraphError> {
+        let mut graph = EntityGraph::new();
+        let mut edges = Vec::new();
+        for shard in shards {
+            for (id, components) in shard.graph.entities {
+                graph.add_entity(id, components)?;
+            }
+            for (id, labels) in shard.graph.labels {
+                for label in labels {
+                    graph.add_label(&id, label)?;
+                }
+            }
+            for (relationship, adjacency_list) in shard.graph.relationships {
+                for (from, neighbors) in &adjacency_list.edges {
+                    for to in neighbors {
+                        edges.push(CutEdge {
+                            relationship: relationship.clone(),
+                            from: from.clone(),
+                            to: to.clone(),
+                            data: adjacency_list.edge_data(from, to).cloned(),
+                        });
+                    }
+                }
+            }
+            edges.extend(shard.cut_edges);
+        }
+        for edge in edges {
+            graph.add_edge_with_optional_data(edge.relationship, edge.from, edge.to, edge.data)?;
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn chain(length: usize) -> TestGraph {
+        let mut graph = TestGraph::new();
+        for index in 0..length {
+            graph
+                .add_entity(format!("entity{index}"), Components::new())
+                .unwrap();
+        }
+        for index in 1..length {
+            graph
+                .add_edge(
+                    "next".to_string(),
+                    format!("entity{}", index - 1),
+                    format!("entity{index}"),
+                )
+                .unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn partition_by_tracks_cut_edges() {
+        let graph = chain(4);
+        let shards = graph.partition_by(|id| id.as_str() < "entity2");
+
+        let left = &shards[&true];
+        assert_eq!(left.graph.entities.len(), 2);
+        assert_eq!(
+            left.cut_edges,
+            vec![CutEdge {
+                relationship: "next".to_string(),
+                from: "entity1".to_string(),
+                to: "entity2".to_string(),
+                data: None,
+            }]
+        );
+        assert!(shards[&false].cut_edges.is_empty());
+    }
+
+    #[test]
+    fn partition_round_trips_through_reassemble() {
+        let mut graph = chain(10);
+        graph.add_label(&"entity3".to_string(), "root").unwrap();
+        graph
+            .set_edge_data(
+                &"next".to_string(),
+                &"entity4".to_string(),
+                &"entity5".to_string(),
+                Value::from(2.5),
+            )
+            .unwrap();
+
+        let shards = graph.partition(3);
+        assert_eq!(shards.len(), 3);
+        let entity_count: usize = shards.iter().map(|shard| shard.graph.entities.len()).sum();
+        assert_eq!(entity_count, 10);
+
+        let reassembled = TestGraph::reassemble(shards).unwrap();
+        assert_eq!(reassembled, graph);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{EntityGraph, EntityGraphError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    hash::Hash,
+};
+
+/// A subgraph that can be stamped into other graphs any number of times with
+/// `EntityGraph::stamp_prefab`. A component value, or a value nested in one, that is a string
+/// of the form `"$name"` is a parameter, replaced when stamping.
+#[derive(Debug, Clone)]
+pub struct Prefab<K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    graph: EntityGraph<String, K, R>,
+}
+
+impl<K, R> Prefab<K, R>
+where
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    pub fn new(graph: EntityGraph<String, K, R>) -> Self {
+        Self { graph }
+    }
+
+    pub fn graph(&self) -> &EntityGraph<String, K, R> {
+        &self.graph
+    }
+
+    /// The names of the prefab's parameters.
+    pub fn parameters(&self) -> BTreeSet<String> {
+        let mut parameters = BTreeSet::new();
+        for components in self.graph.entities.values() {
+            for value in components.values() {
+                collect_parameters(value, &mut parameters);
+            }
+        }
+        parameters
+    }
+}
+
+fn parameter_name(value: &Value) -> Option<&str> {
+    value.as_str()?.strip_prefix('$')
+}
+
+fn collect_parameters(value: &Value, parameters: &mut BTreeSet<String>) {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_parameters(value, parameters)),
+        Value::Object(values) => values
+            .values()
+            .for_each(|value| collect_parameters(value, parameters)),
+        value => {
+            if let Some(name) = parameter_name(value) {
+                parameters.insert(name.to_string());
+            }
+        }
+    }
+}
+
+fn substitute(value: &mut Value, arguments: &HashMap<String, Value>) -> Result<(), String> {
+    match value {
+        Value::Array(values) => values
+            .iter_mut()
+            .try_for_each(|value| substitute(value, arguments)),
+        Value::Object(values) => values
+            .values_mut()
+            .try_for_each(|value| substitute(value, arguments)),
+        _ => {
+            if let Some(name) = parameter_name(value) {
+                *value = arguments
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| name.to_string())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Prefabs by name.
+#[derive(Debug, Clone)]
+pub struct PrefabRegistry<K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    prefabs: HashMap<String, Prefab<K, R>>,
+}
+
+impl<K: Eq + Hash + Clone, R: Eq + Hash + Clone> Default for PrefabRegistry<K, R> {
+    fn default() -> Self {
+        Self {
+            prefabs: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, R: Eq + Hash + Clone> PrefabRegistry<K, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a prefab, returning the one it replaces.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        prefab: Prefab<K, R>,
+    ) -> Option<Prefab<K, R>> {
+        self.prefabs.insert(name.into(), prefab)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab<K, R>> {
+        self.prefabs.get(name)
+    }
+}
+
+impl<K, R> EntityGraph<String, K, R>
+where
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Adds a copy of the prefab's entities, labels and edges with `prefix` prepended to their
+    /// IDs and its parameters replaced by `arguments`, returning the new IDs. Fails without
+    /// changing the graph if an argument is missing or a new ID is already taken.
+    pub fn stamp_prefab(
+        &mut self,
+        prefab: &Prefab<K, R>,
+        prefix: &str,
+        arguments: &HashMap<String, Value>,
+    ) -> Result<Vec<String>, EntityGraphError> {
+        let mut stamp = prefab.graph.clone();
+        for components in stamp.entities.values_mut() {
+            for value in components.values_mut() {
+                substitute(value, arguments)
+                    .map_err(|parameter| EntityGraphError::MissingArgument { parameter })?;
+            }
+        }
+        stamp.prefix_ids(prefix)?;
+        if let Some(id) = stamp
+            .entities
+            .keys()
+            .find(|id| self.entities.contains_key(*id))
+        {
+            return Err(EntityGraphError::entity_already_exists(id));
+        }
+
+        let ids = stamp.entities.keys().cloned().collect();
+        self.add_entities(stamp.entities)?;
+        for (id, labels) in stamp.labels {
+            for label in labels {
+                self.add_label(&id, label)?;
+            }
+        }
+        for (relationship, adjacency_list) in stamp.relationships {
+            for (from, neighbors) in &adjacency_list.edges {
+                for to in neighbors {
+                    self.add_edge_with_optional_data(
+                        relationship.clone(),
+                        from.clone(),
+                        to.clone(),
+                        adjacency_list.edge_data(from, to).cloned(),
+                    )?;
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::json;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn module() -> Prefab<String, String> {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "section".to_string(),
+                Components::from([("index".to_string(), Value::from("$index"))]),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([(
+                    "style".to_string(),
+                    json!({ "color": "$color", "brightness": 100 }),
+                )]),
+            )
+            .unwrap();
+        graph
+            .add_edge(
+                "contains".to_string(),
+                "section".to_string(),
+                "led".to_string(),
+            )
+            .unwrap();
+        graph.add_label(&"led".to_string(), "output").unwrap();
+        Prefab::new(graph)
+    }
+
+    #[test]
+    fn prefabs_are_stamped_with_prefixes() {
+        let mut registry = PrefabRegistry::new();
+        registry.register("module", module());
+        let prefab = registry.get("module").unwrap();
+        assert_eq!(
+            prefab.parameters().into_iter().collect::<Vec<_>>(),
+            ["color", "index"]
+        );
+
+        let mut graph = TestGraph::new();
+        for (index, color) in [(1, "red"), (2, "blue")] {
+            let arguments = HashMap::from([
+                ("index".to_string(), Value::from(index)),
+                ("color".to_string(), Value::from(color)),
+            ]);
+            let mut ids = graph
+                .stamp_prefab(prefab, &format!("module{index}/"), &arguments)
+                .unwrap();
+            ids.sort();
+            assert_eq!(
+                ids,
+                [
+                    format!("module{index}/led"),
+                    format!("module{index}/section")
+                ]
+            );
+        }
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(
+            graph.get_component(&"module2/section".to_string(), &"index".to_string()),
+            Some(&Value::from(2))
+        );
+        assert_eq!(
+            graph.get_component(&"module1/led".to_string(), &"style".to_string()),
+            Some(&json!({ "color": "red", "brightness": 100 }))
+        );
+        assert!(graph.contains_edge(
+            &"contains".to_string(),
+            &"module2/section".to_string(),
+            &"module2/led".to_string()
+        ));
+        assert!(graph.has_label(&"module1/led".to_string(), "output"));
+
+        let arguments = HashMap::from([("index".to_string(), Value::from(3))]);
+        assert!(matches!(
+            graph.stamp_prefab(prefab, "module3/", &arguments),
+            Err(EntityGraphError::MissingArgument { .. })
+        ));
+        let arguments = HashMap::from([
+            ("index".to_string(), Value::from(1)),
+            ("color".to_string(), Value::from("green")),
+        ]);
+        assert!(matches!(
+            graph.stamp_prefab(prefab, "module1/", &arguments),
+            Err(EntityGraphError::EntityAlreadyExists { .. })
+        ));
+        assert_eq!(graph.node_count(), 4);
+    }
+}
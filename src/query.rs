@@ -0,0 +1,174 @@
+use crate::{Components, EntityGraph};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashSet, fmt::Display, hash::Hash};
+
+type EntityFilter<'a, ID, K> = Box<dyn Fn(&ID, &Components<K>) -> bool + 'a>;
+
+/// Selects the entities of a graph matching every condition added to it, see
+/// `EntityGraph::query`.
+pub struct Query<'a, ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    graph: &'a EntityGraph<ID, K, R>,
+    filters: Vec<EntityFilter<'a, ID, K>>,
+}
+
+impl<'a, ID, K, R> Query<'a, ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    pub fn has_component(mut self, component_key: &'a K) -> Self {
+        self.filters.push(Box::new(|_, components| {
+            components.contains_key(component_key)
+        }));
+        self
+    }
+
+    /// Entities with a `component_key` component whose value passes `predicate`.
+    pub fn where_value<F>(mut self, component_key: &'a K, predicate: F) -> Self
+    where
+        F: Fn(&Value) -> bool + 'a,
+    {
+        self.filters.push(Box::new(move |_, components| {
+            components.get(component_key).is_some_and(&predicate)
+        }));
+        self
+    }
+
+    pub fn has_label(mut self, label: &'a str) -> Self {
+        let graph = self.graph;
+        self.filters
+            .push(Box::new(move |id, _| graph.has_label(id, label)));
+        self
+    }
+
+    /// Entities that `id` has a `relationship` edge to.
+    pub fn related_to(mut self, id: &ID, relationship: &R) -> Self {
+        let neighbors = self
+            .graph
+            .get_neighbors_by_relationship(id, relationship)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<HashSet<_>>();
+        self.filters
+            .push(Box::new(move |id, _| neighbors.contains(id)));
+        self
+    }
+
+    /// Entities for which `predicate(id, components)` returns `true`.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&ID, &Components<K>) -> bool + 'a,
+    {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    /// The matching entities with their components, in iteration order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a ID, &'a Components<K>)> + '_ {
+        self.graph
+            .entities
+            .iter()
+            .filter(|(id, components)| self.filters.iter().all(|filter| filter(id, components)))
+    }
+
+    /// The IDs of the matching entities, in iteration order.
+    pub fn collect(&self) -> Vec<ID> {
+        self.iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// A query matching every entity, to be narrowed down with its methods.
+    pub fn query(&self) -> Query<'_, ID, K, R> {
+        Query {
+            graph: self,
+            filters: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn query_combines_conditions() {
+        let mut graph = TestGraph::new();
+        for (id, voltage) in [
+            ("psu", None),
+            ("led1", Some(5)),
+            ("led2", Some(12)),
+            ("led3", Some(5)),
+        ] {
+            let mut components = Components::from([("name".to_string(), Value::from(id))]);
+            if let Some(voltage) = voltage {
+                components.insert("voltage".to_string(), Value::from(voltage));
+            }
+            graph.add_entity(id.to_string(), components).unwrap();
+        }
+        let powers = "powers".to_string();
+        for led in ["led1", "led2"] {
+            graph
+                .add_edge(powers.clone(), "psu".to_string(), led.to_string())
+                .unwrap();
+        }
+        graph.add_label(&"led2".to_string(), "status").unwrap();
+
+        let voltage = "voltage".to_string();
+        let sorted = |mut ids: Vec<String>| {
+            ids.sort();
+            ids
+        };
+        assert_eq!(graph.query().count(), 4);
+        assert_eq!(
+            sorted(graph.query().has_component(&voltage).collect()),
+            ["led1", "led2", "led3"]
+        );
+        assert_eq!(
+            sorted(
+                graph
+                    .query()
+                    .where_value(&voltage, |value| value == 5)
+                    .collect()
+            ),
+            ["led1", "led3"]
+        );
+        assert_eq!(
+            graph
+                .query()
+                .where_value(&voltage, |value| value == 5)
+                .related_to(&"psu".to_string(), &powers)
+                .collect(),
+            ["led1"]
+        );
+        assert_eq!(
+            graph
+                .query()
+                .has_label("status")
+                .filter(|id, _| id.starts_with("led"))
+                .collect(),
+            ["led2"]
+        );
+        assert_eq!(
+            graph
+                .query()
+                .related_to(&"led1".to_string(), &powers)
+                .count(),
+            0
+        );
+    }
+}
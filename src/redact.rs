@@ -0,0 +1,269 @@
+use crate::{Components, EntityGraph};
+use serde::{ser::SerializeMap, ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::{error::Error, fmt::Display, hash::Hash};
+
+/// A read-only view of an `EntityGraph` that hides every component for which `keep` returns
+/// `false`. Entities, labels and edges remain visible, and the view serializes in the same
+/// format as the graph, edge policies and metadata included, so it can be loaded with
+/// `deserialize_with_registry`.
+pub struct FilteredView<'a, ID, K, R, F>
+where
+    ID: Eq + Hash + Clone,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone,
+{
+    graph: &'a EntityGraph<ID, K, R>,
+    keep: F,
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// A view that only exposes the components for which `keep(entity_id, component_key)`
+    /// returns `true`.
+    pub fn filtered<F>(&self, keep: F) -> FilteredView<'_, ID, K, R, F>
+    where
+        F: Fn(&ID, &K) -> bool,
+    {
+        FilteredView { graph: self, keep }
+    }
+
+    /// Serializes the graph like `serialize`, omitting components rejected by `keep`.
+    pub fn serialize_filtered<F>(&self, keep: F) -> Result<String, Box<dyn Error>>
+    where
+        F: Fn(&ID, &K) -> bool,
+    {
+        serde_json::to_string(&self.filtered(keep)).map_err(Into::into)
+    }
+}
+
+impl<'a, ID, K, R, F> FilteredView<'a, ID, K, R, F>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    F: Fn(&ID, &K) -> bool,
+{
+    pub fn contains_entity(&self, entity_id: &ID) -> bool {
+        self.graph.entities.contains_key(entity_id)
+    }
+
+    pub fn get_component(&self, entity_id: &ID, component_key: &K) -> Option<&'a Value> {
+        if !(self.keep)(entity_id, component_key) {
+            return None;
+        }
+        self.graph.get_component(entity_id, component_key)
+    }
+
+    /// The visible components of an entity.
+    pub fn components<'v>(
+        &'v self,
+        entity_id: &'v ID,
+    ) -> impl Iterator<Item = (&'a K, &'a Value)> + 'v {
+        self.graph
+            .entities
+            .get(entity_id)
+            .into_iter()
+            .flatten()
+            .filter(move |(key, _)| (self.keep)(entity_id, key))
+    }
+
+    pub fn get_neighbors(&self, entity_id: &ID) -> Option<Vec<&'a ID>> {
+        self.graph.get_neighbors(entity_id)
+    }
+
+    pub fn get_neighbors_by_relationship(
+        &self,
+        entity_id: &ID,
+        relationship_key: &R,
+    ) -> Option<&'a Vec<ID>> {
+        self.graph
+            .get_neighbors_by_relationship(entity_id, relationship_key)
+    }
+}
+
+struct FilteredEntities<'v, 'a, ID, K, R, F>(&'v FilteredView<'a, ID, K, R, F>)
+where
+    ID: Eq + Hash + Clone,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone;
+
+struct FilteredComponents<'v, 'a, ID, K, F> {
+    id: &'a ID,
+    components: &'a Components<K>,
+    keep: &'v F,
+}
+
+impl<ID, K, F> Serialize for FilteredComponents<'_, '_, ID, K, F>
+where
+    K: Serialize,
+    F: Fn(&ID, &K) -> bool,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, value) in self.components {
+            if (self.keep)(self.id, key) {
+                map.serialize_entry(key, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+impl<ID, K, R, F> Serialize for FilteredEntities<'_, '_, ID, K, R, F>
+where
+    ID: Eq + Hash + Clone + Serialize,
+    K: Eq + Hash + Clone + Serialize,
+    R: Eq + Hash + Clone,
+    F: Fn(&ID, &K) -> bool,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let view = self.0;
+        let mut map = serializer.serialize_map(Some(view.graph.entities.len()))?;
+        for (id, components) in &view.graph.entities {
+            map.serialize_entry(
+                id,
+                &FilteredComponents {
+                    id,
+                    components,
+                    keep: &view.keep,
+                },
+            )?;
+        }
+        map.end()
+    }
+}
+
+impl<ID, K, R, F> Serialize for FilteredView<'_, ID, K, R, F>
+where
+    ID: Eq + Hash + Clone + Serialize,
+    K: Eq + Hash + Clone + Serialize,
+    R: Eq + Hash + Clone + Serialize,
+    F: Fn(&ID, &K) -> bool,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let graph = self.graph;
+        let mut state = serializer.serialize_struct("EntityGraph", 5)?;
+        state.serialize_field("entities", &FilteredEntities(self))?;
+        state.serialize_field("relationships", &graph.relationships)?;
+        if graph.labels.is_empty() {
+            state.skip_field("labels")?;
+        } else {
+            state.serialize_field("labels", &graph.labels)?;
+        }
+        if graph.edge_policies.is_empty() {
+            state.skip_field("edge_policies")?;
+        } else {
+            state.serialize_field("edge_policies", &graph.edge_policies)?;
+        }
+        match &graph.metadata {
+            Some(metadata) => state.serialize_field("metadata", metadata)?,
+            None => state.skip_field("metadata")?,
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DuplicateEdgePolicy, EntityGraph, Metadata, TypeRegistry};
+    use serde_json::Value;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn device_graph() -> TestGraph {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "device".to_string(),
+                vec![
+                    ("name".to_string(), Value::from("controller")),
+                    ("password".to_string(), Value::from("hunter2")),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .unwrap();
+        graph
+            .add_entity(
+                "led".to_string(),
+                vec![("name".to_string(), Value::from("led"))]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap();
+        graph
+            .add_edge(
+                "contains".to_string(),
+                "device".to_string(),
+                "led".to_string(),
+            )
+            .unwrap();
+        graph
+    }
+
+    #[test]
+    fn filtered_view_hides_components() {
+        let graph = device_graph();
+        let view = graph.filtered(|_, key| key != "password");
+
+        let device = "device".to_string();
+        assert!(view
+            .get_component(&device, &"password".to_string())
+            .is_none());
+        assert_eq!(
+            view.get_component(&device, &"name".to_string()),
+            Some(&Value::from("controller"))
+        );
+        assert_eq!(view.components(&device).count(), 1);
+        assert_eq!(view.get_neighbors(&device).unwrap(), vec!["led"]);
+    }
+
+    #[test]
+    fn serialize_filtered_keeps_topology() {
+        let graph = device_graph();
+        let serialized = graph
+            .serialize_filtered(|_, key| key != "password")
+            .unwrap();
+        assert!(!serialized.contains("hunter2"));
+
+        let mut registry = TypeRegistry::new();
+        registry.register::<String>("name");
+        let restored = TestGraph::deserialize_with_registry(&serialized, &registry).unwrap();
+        assert!(restored
+            .get_component(&"device".to_string(), &"password".to_string())
+            .is_none());
+        assert_eq!(
+            restored.get_neighbors(&"device".to_string()).unwrap(),
+            vec!["led"]
+        );
+    }
+
+    #[test]
+    fn serialize_filtered_keeps_edge_policies_and_metadata() {
+        let mut graph = device_graph();
+        let serialized = graph
+            .serialize_filtered(|_, key| key != "password")
+            .unwrap();
+        assert!(!serialized.contains("edge_policies"));
+        assert!(!serialized.contains("metadata"));
+
+        graph.set_duplicate_edge_policy("contains".to_string(), DuplicateEdgePolicy::DedupSilently);
+        graph.set_metadata(Metadata::default().title("devices"));
+        let serialized = graph
+            .serialize_filtered(|_, key| key != "password")
+            .unwrap();
+        let mut registry = TypeRegistry::new();
+        registry.register::<String>("name");
+        let restored = TestGraph::deserialize_with_registry(&serialized, &registry).unwrap();
+        assert_eq!(
+            restored.duplicate_edge_policy(&"contains".to_string()),
+            DuplicateEdgePolicy::DedupSilently
+        );
+        assert_eq!(restored.metadata(), graph.metadata());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{ChangeSet, Components, EntityChange, EntityGraph, EntityGraphError, LabelSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashSet, fmt::Display, hash::Hash};
+
+/// An entity as held by a `GraphStore`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteNode<K: Eq + Hash> {
+    pub components: Components<K>,
+    pub labels: LabelSet,
+}
+
+/// An outgoing edge as held by a `GraphStore`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteEdge<ID, R> {
+    pub relationship: R,
+    pub to: ID,
+    pub data: Option<Value>,
+}
+
+/// A backend holding the authoritative copy of a graph, such as a property graph database,
+/// that a `RemoteGraph` caches locally.
+pub trait GraphStore<ID, K: Eq + Hash, R> {
+    type Error: From<EntityGraphError>;
+
+    /// The entity with the given ID, or `None` if the store doesn't have it.
+    fn fetch_node(&self, id: &ID) -> Result<Option<RemoteNode<K>>, Self::Error>;
+
+    /// Every outgoing edge of the entity with the given ID.
+    fn fetch_neighbors(&self, id: &ID) -> Result<Vec<RemoteEdge<ID, R>>, Self::Error>;
+
+    /// Applies changes made locally, in the form of `EntityGraph::import_changes`.
+    fn push(&mut self, changes: ChangeSet<ID, K, R>) -> Result<(), Self::Error>;
+}
+
+/// A reference `GraphStore` backed by an `EntityGraph`, for tests and prototyping.
+#[derive(Debug, Clone)]
+pub struct InMemoryStore<ID: Eq + Hash + Clone, K: Eq + Hash + Clone, R: Eq + Hash + Clone> {
+    pub graph: EntityGraph<ID, K, R>,
+}
+
+impl<ID, K, R> InMemoryStore<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    pub fn new(graph: EntityGraph<ID, K, R>) -> Self {
+        Self { graph }
+    }
+}
+
+impl<ID, K, R> GraphStore<ID, K, R> for InMemoryStore<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    type Error = EntityGraphError;
+
+    fn fetch_node(&self, id: &ID) -> Result<Option<RemoteNode<K>>, Self::Error> {
+        Ok(self.graph.entities.get(id).map(|components| RemoteNode {
+            components: components.clone(),
+            labels: self.graph.labels.get(id).cloned().unwrap_or_default(),
+        }))
+    }
+
+    fn fetch_neighbors(&self, id: &ID) -> Result<Vec<RemoteEdge<ID, R>>, Self::Error> {
+        Ok(self
+            .graph
+            .relationships
+            .iter()
+            .flat_map(|(relationship, adjacency_list)| {
+                adjacency_list
+                    .edges
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .map(move |to| RemoteEdge {
+                        relationship: relationship.clone(),
+                        to: to.clone(),
+                        data: adjacency_list.edge_data(id, to).cloned(),
+                    })
+            })
+            .collect())
+    }
+
+    fn push(&mut self, changes: ChangeSet<ID, K, R>) -> Result<(), Self::Error> {
+        self.graph.import_changes(changes)
+    }
+}
+
+/// An `EntityGraph` used as a local cache and write buffer over a `GraphStore`. Entities are
+/// only read from the store by `pull` and local changes are only written by `push`.
+pub struct RemoteGraph<ID, K, R, S>
+where
+    ID: Eq + Hash + Clone,
+    K: Eq + Hash + Clone,
+    R: Eq + Hash + Clone,
+{
+    graph: EntityGraph<ID, K, R>,
+    store: S,
+    // The graph version as of the last push.
+    pushed: u64,
+}
+
+impl<ID, K, R, S> RemoteGraph<ID, K, R, S>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    S: GraphStore<ID, K, R>,
+{
+    /// An empty cache over `store`.
+    pub fn new(store: S) -> Self {
+        let graph = EntityGraph::new();
+        Self {
+            pushed: graph.version(),
+            graph,
+            store,
+        }
+    }
+
+    pub fn graph(&self) -> &EntityGraph<ID, K, R> {
+        &self.graph
+    }
+
+    /// The local graph, whose changes are written to the store by the next `push`.
+    pub fn graph_mut(&mut self) -> &mut EntityGraph<ID, K, R> {
+        &mut self.graph
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Whether the graph has changes that haven't been pushed.
+    pub fn has_unpushed_changes(&self) -> bool {
+        self.graph.version() != self.pushed
+    }
+
+    /// Replaces the local copies of the given entities and their outgoing edges with the
+    /// store's. Targets of those edges missing locally are fetched without their own edges,
+    /// and entities the store doesn't have are removed locally. Pulled entities are only sent
+    /// back by a later `push` if there were unpushed changes at the time of the pull.
+    pub fn pull(&mut self, ids: impl IntoIterator<Item = ID>) -> Result<(), S::Error> {
+        let mut changes = ChangeSet {
+            since: 0,
+            version: 0,
+            changed: Vec::new(),
+            removed: Vec::new(),
+        };
+        let mut fetched = HashSet::new();
+        let mut targets = Vec::new();
+        for id in ids {
+            let Some(node) = self.store.fetch_node(&id)? else {
+                changes.removed.push(id);
+                continue;
+            };
+            let mut edges = Vec::<(R, Vec<ID>)>::new();
+            let mut edge_data = Vec::new();
+            for edge in self.store.fetch_neighbors(&id)? {
+                targets.push(edge.to.clone());
+                if let Some(data) = edge.data {
+                    edge_data.push((edge.relationship.clone(), edge.to.clone(), data));
+                }
+                match edges
+                    .iter_mut()
+                    .find(|(relationship, _)| *relationship == edge.relationship)
+                {
+                    Some((_, neighbors)) => neighbors.push(edge.to),
+                    None => edges.push((edge.relationship, vec![edge.to])),
+                }
+            }
+            fetched.insert(id.clone());
+            changes.changed.push(EntityChange {
+                id,
+                components: node.components,
+                labels: node.labels,
+                edges,
+                edge_data,
+            });
+        }
+        for id in targets {
+            if self.graph.entities.contains_key(&id) || fetched.contains(&id) {
+                continue;
+            }
+            if let Some(node) = self.store.fetch_node(&id)? {
+                fetched.insert(id.clone());
+                changes.changed.push(EntityChange {
+                    id,
+                    components: node.components,
+                    labels: node.labels,
+                    edges: Vec::new(),
+                    edge_data: Vec::new(),
+                });
+            }
+        }
+        // Drop edges to targets the store doesn't have, which couldn't be added.
+        let present = |id: &ID| fetched.contains(id) || self.graph.entities.contains_key(id);
+        for change in &mut changes.changed {
+            for (_, neighbors) in &mut change.edges {
+                neighbors.retain(|to| present(to));
+            }
+            change.edge_data.retain(|(_, to, _)| present(to));
+        }
+
+        let clean = !self.has_unpushed_changes();
+        self.graph.import_changes(changes)?;
+        if clean {
+            self.pushed = self.graph.version();
+        }
+        Ok(())
+    }
+
+    /// Writes every local change since the last push to the store.
+    pub fn push(&mut self) -> Result<(), S::Error> {
+        let changes = self.graph.export_changes_since(self.pushed);
+        let version = changes.version;
+        self.store.push(changes)?;
+        self.pushed = version;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn pull_and_push_sync_with_store() {
+        let mut remote = TestGraph::new();
+        for (id, value) in [("plant", 1), ("line1", 2), ("press", 3)] {
+            remote
+                .add_entity(
+                    id.to_string(),
+                    Components::from([("value".to_string(), Value::from(value))]),
+                )
+                .unwrap();
+        }
+        let contains = "contains".to_string();
+        remote
+            .add_edge_with_data(
+                contains.clone(),
+                "plant".to_string(),
+                "line1".to_string(),
+                Value::from("main"),
+            )
+            .unwrap();
+        remote
+            .add_edge(contains.clone(), "line1".to_string(), "press".to_string())
+            .unwrap();
+        remote.add_label(&"plant".to_string(), "site").unwrap();
+
+        let mut cache = RemoteGraph::new(InMemoryStore::new(remote));
+        cache.pull(["plant".to_string()]).unwrap();
+        let graph = cache.graph();
+        assert_eq!(graph.node_count(), 2);
+        assert!(graph.has_label(&"plant".to_string(), "site"));
+        assert_eq!(
+            graph.get_edge_data(&contains, &"plant".to_string(), &"line1".to_string()),
+            Some(&Value::from("main"))
+        );
+        assert!(!graph.contains_edge(&contains, &"line1".to_string(), &"press".to_string()));
+        assert!(!cache.has_unpushed_changes());
+
+        cache.pull(["line1".to_string()]).unwrap();
+        assert!(cache
+            .graph()
+            .contains_edge(&contains, &"line1".to_string(), &"press".to_string()));
+
+        let graph = cache.graph_mut();
+        graph
+            .add_entity("robot".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge(contains.clone(), "line1".to_string(), "robot".to_string())
+            .unwrap();
+        assert!(cache.has_unpushed_changes());
+        cache.push().unwrap();
+        assert!(!cache.has_unpushed_changes());
+        assert_eq!(&cache.store().graph, cache.graph());
+
+        cache
+            .pull(["robot".to_string(), "missing".to_string()])
+            .unwrap();
+        assert_eq!(&cache.store().graph, cache.graph());
+    }
+}
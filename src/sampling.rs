@@ -0,0 +1,246 @@
+use crate::{generators::SplitMix64, Components, EntityGraph};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    hash::Hash,
+};
+
+/// How `sample_subgraph` picks entities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleStrategy {
+    /// Entities chosen uniformly at random.
+    RandomNode,
+    /// A random walk over edges in either direction, jumping back to its start with
+    /// `restart_probability` at each step and to a fresh start when it gets stuck.
+    RandomWalk { restart_probability: f64 },
+    /// Forest fire sampling: from a random start, each unburned neighbor of a burning entity
+    /// catches fire with `burn_probability`; a new fire starts when one burns out.
+    ForestFire { burn_probability: f64 },
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// A graph with up to `node_count` entities picked by `strategy`, with their components,
+    /// labels and the edges between them. The same seed on the same graph gives the same
+    /// sample.
+    pub fn sample_subgraph(&self, node_count: usize, strategy: SampleStrategy, seed: u64) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        // Sort so the sample doesn't depend on map iteration order.
+        let mut ids = self.entities.keys().collect::<Vec<_>>();
+        ids.sort_by_cached_key(|id| serde_json::to_string(id).unwrap_or_default());
+        let node_count = node_count.min(ids.len());
+
+        let mut selected = HashSet::new();
+        match strategy {
+            SampleStrategy::RandomNode => {
+                // Partial Fisher-Yates shuffle.
+                for index in 0..node_count {
+                    let swap = index + rng.below(ids.len() - index);
+                    ids.swap(index, swap);
+                    selected.insert(ids[index]);
+                }
+            }
+            SampleStrategy::RandomWalk {
+                restart_probability,
+            } => {
+                let mut start = ids.get(rng.below(ids.len().max(1))).copied();
+                let mut current = start;
+                let mut stalled = 0;
+                while selected.len() < node_count {
+                    let Some(id) = current else { break };
+                    if selected.insert(id) {
+                        stalled = 0;
+                    } else {
+                        stalled += 1;
+                    }
+                    let neighbors = self.undirected_neighbors(id);
+                    if neighbors.is_empty() || stalled > 100 {
+                        start = ids.get(rng.below(ids.len())).copied();
+                        current = start;
+                        stalled = 0;
+                    } else if rng.next_f64() < restart_probability {
+                        current = start;
+                    } else {
+                        current = neighbors.get(rng.below(neighbors.len())).copied();
+                    }
+                }
+            }
+            SampleStrategy::ForestFire { burn_probability } => {
+                let mut burning = VecDeque::new();
+                while selected.len() < node_count {
+                    let Some(id) = burning.pop_front() else {
+                        let unburned = ids
+                            .iter()
+                            .copied()
+                            .filter(|id| !selected.contains(id))
+                            .collect::<Vec<_>>();
+                        let start = unburned[rng.below(unburned.len())];
+                        selected.insert(start);
+                        burning.push_back(start);
+                        continue;
+                    };
+                    for neighbor in self.undirected_neighbors(id) {
+                        if selected.len() == node_count {
+                            break;
+                        }
+                        if !selected.contains(neighbor) && rng.next_f64() < burn_probability {
+                            selected.insert(neighbor);
+                            burning.push_back(neighbor);
+                        }
+                    }
+                }
+            }
+        }
+        self.induced(&selected)
+    }
+
+    /// Neighbors over outgoing and incoming edges of every relationship, in a stable order.
+    fn undirected_neighbors(&self, id: &ID) -> Vec<&ID> {
+        let mut neighbors = self.get_neighbors(id).unwrap_or_default();
+        neighbors.extend(self.get_incoming_neighbors(id).unwrap_or_default());
+        neighbors.sort_by_cached_key(|id| serde_json::to_string(id).unwrap_or_default());
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// The entities for which `keep(id, components)` returns `true`, with their components,
+    /// labels and every edge between them.
+    pub fn subgraph<F>(&self, mut keep: F) -> Self
+    where
+        F: FnMut(&ID, &Components<K>) -> bool,
+    {
+        let selected = self
+            .entities
+            .iter()
+            .filter(|(id, components)| keep(id, components))
+            .map(|(id, _)| id)
+            .collect();
+        self.induced(&selected)
+    }
+
+    /// The entities with an edge of `relationship`, in either direction, with their components,
+    /// labels and the edges of that relationship between them.
+    pub fn subgraph_by_relationship(&self, relationship: &R) -> Self {
+        let Some(adjacency_list) = self.relationships.get(relationship) else {
+            return EntityGraph::new();
+        };
+        let selected = adjacency_list
+            .edges
+            .iter()
+            .flat_map(|(from, neighbors)| std::iter::once(from).chain(neighbors))
+            .collect();
+        let mut graph = self.induced(&selected);
+        let others = graph
+            .relationships
+            .keys()
+            .filter(|other| *other != relationship)
+            .cloned()
+            .collect::<Vec<_>>();
+        for other in others {
+            graph.remove_relationship(&other).unwrap();
+        }
+        graph
+    }
+
+    /// The selected entities with their components and labels, and every edge between them.
+    pub(crate) fn induced(&self, selected: &HashSet<&ID>) -> Self {
+        let mut graph = EntityGraph::new();
+        graph.edge_policies = self.edge_policies.clone();
+        for (id, components) in &self.entities {
+            if selected.contains(id) {
+                graph.add_entity(id.clone(), components.clone()).unwrap();
+                for label in self.labels(id).into_iter().flatten() {
+                    graph.add_label(id, label.clone()).unwrap();
+                }
+            }
+        }
+        for (relationship, adjacency_list) in &self.relationships {
+            for (from, neighbors) in &adjacency_list.edges {
+                if !selected.contains(from) {
+                    continue;
+                }
+                for to in neighbors.iter().filter(|to| selected.contains(to)) {
+                    graph
+                        .add_edge_with_optional_data(
+                            relationship.clone(),
+                            from.clone(),
+                            to.clone(),
+                            adjacency_list.edge_data(from, to).cloned(),
+                        )
+                        .unwrap();
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::Generator;
+
+    #[test]
+    fn samples_have_requested_size() {
+        let graph = Generator::new(4).barabasi_albert(200, 2);
+        for strategy in [
+            SampleStrategy::RandomNode,
+            SampleStrategy::RandomWalk {
+                restart_probability: 0.15,
+            },
+            SampleStrategy::ForestFire {
+                burn_probability: 0.4,
+            },
+        ] {
+            let sample = graph.sample_subgraph(30, strategy, 9);
+            assert_eq!(sample.entities.len(), 30);
+            assert_eq!(sample, graph.sample_subgraph(30, strategy, 9));
+            for adjacency_list in sample.relationships.values() {
+                for (from, neighbors) in &adjacency_list.edges {
+                    for to in neighbors {
+                        assert!(sample.entities.contains_key(to));
+                        assert!(graph.get_neighbors(from).unwrap().contains(&to));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_larger_than_graph() {
+        let graph = Generator::new(0).grid(3, 3);
+        let sample = graph.sample_subgraph(
+            100,
+            SampleStrategy::ForestFire {
+                burn_probability: 0.5,
+            },
+            1,
+        );
+        assert_eq!(sample, graph);
+        assert!(Generator::new(0)
+            .grid(0, 0)
+            .sample_subgraph(5, SampleStrategy::RandomNode, 1)
+            .entities
+            .is_empty());
+    }
+
+    #[test]
+    fn subgraphs_by_predicate_and_relationship() {
+        let mut graph = Generator::new(2).grid(3, 3);
+        graph.add_edge("wire".to_string(), 0, 8).unwrap();
+
+        let even = graph.subgraph(|id, _| id % 2 == 0);
+        assert_eq!(even.entities.len(), 5);
+        assert_eq!(even.get_neighbors(&0).unwrap(), vec![&8]);
+
+        let wiring = graph.subgraph_by_relationship(&"wire".to_string());
+        assert_eq!(wiring.entities.len(), 2);
+        assert_eq!(wiring.relationships.len(), 1);
+        assert_eq!(wiring.get_incoming_neighbors(&8).unwrap(), vec![&0]);
+    }
+}
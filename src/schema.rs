@@ -0,0 +1,393 @@
+use crate::{graph::id_string, EntityGraph, KindRegistry};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt, fmt::Display, hash::Hash};
+
+/// The JSON type a component value must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueShape {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl ValueShape {
+    pub fn matches(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (ValueShape::Null, Value::Null)
+                | (ValueShape::Bool, Value::Bool(_))
+                | (ValueShape::Number, Value::Number(_))
+                | (ValueShape::String, Value::String(_))
+                | (ValueShape::Array, Value::Array(_))
+                | (ValueShape::Object, Value::Object(_))
+        )
+    }
+}
+
+/// How many outgoing edges of a relationship the entities of a kind must have, and what their
+/// targets must look like.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelationshipRule<K> {
+    pub min: usize,
+    /// The most edges allowed, or `None` for no limit.
+    pub max: Option<usize>,
+    /// Components every target must have.
+    pub target_components: Vec<K>,
+}
+
+impl<K> RelationshipRule<K> {
+    pub fn new(min: usize, max: Option<usize>) -> Self {
+        Self {
+            min,
+            max,
+            target_components: Vec::new(),
+        }
+    }
+
+    pub fn target_component(mut self, component_key: K) -> Self {
+        self.target_components.push(component_key);
+        self
+    }
+}
+
+/// Constraints a graph can be checked against with `EntityGraph::validate`. Entities are
+/// matched to kinds by label, as `EntityGraph::add_entity_of_kind` does, and value shapes
+/// apply to every entity.
+#[derive(Debug, Clone)]
+pub struct GraphSchema<K, R> {
+    kinds: KindRegistry<K, R>,
+    shapes: HashMap<K, ValueShape>,
+    rules: HashMap<String, Vec<(R, RelationshipRule<K>)>>,
+}
+
+impl<K, R> Default for GraphSchema<K, R> {
+    fn default() -> Self {
+        Self {
+            kinds: KindRegistry::default(),
+            shapes: HashMap::new(),
+            rules: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, R> GraphSchema<K, R> {
+    /// A schema requiring the components and relationships of `kinds`.
+    pub fn new(kinds: KindRegistry<K, R>) -> Self {
+        Self {
+            kinds,
+            ..Self::default()
+        }
+    }
+
+    pub fn kinds(&self) -> &KindRegistry<K, R> {
+        &self.kinds
+    }
+
+    pub fn kinds_mut(&mut self) -> &mut KindRegistry<K, R> {
+        &mut self.kinds
+    }
+
+    /// Requires every `component_key` value to have the given shape.
+    pub fn shape(&mut self, component_key: K, shape: ValueShape) -> &mut Self {
+        self.shapes.insert(component_key, shape);
+        self
+    }
+
+    /// Adds a rule for the `relationship` edges of entities of `kind`.
+    pub fn relationship(
+        &mut self,
+        kind: impl Into<String>,
+        relationship: R,
+        rule: RelationshipRule<K>,
+    ) -> &mut Self {
+        self.rules
+            .entry(kind.into())
+            .or_default()
+            .push((relationship, rule));
+        self
+    }
+}
+
+/// A way a graph breaks its `GraphSchema`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation<ID, K, R> {
+    MissingComponent {
+        id: ID,
+        component: K,
+    },
+    InvalidValue {
+        id: ID,
+        component: K,
+        expected: ValueShape,
+    },
+    RelationshipNotAllowed {
+        id: ID,
+        relationship: R,
+    },
+    Cardinality {
+        id: ID,
+        relationship: R,
+        count: usize,
+    },
+    InvalidTarget {
+        id: ID,
+        relationship: R,
+        target: ID,
+        component: K,
+    },
+}
+
+impl<ID: Serialize, K: Display, R: Display> Display for Violation<ID, K, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MissingComponent { id, component } => write!(
+                f,
+                "Entity {} is missing component '{}'",
+                id_string(id),
+                component
+            ),
+            Violation::InvalidValue {
+                id,
+                component,
+                expected,
+            } => write!(
+                f,
+                "Component '{}' of entity {} should be {:?}",
+                component,
+                id_string(id),
+                expected
+            ),
+            Violation::RelationshipNotAllowed { id, relationship } => write!(
+                f,
+                "Entity {} cannot have '{}' edges",
+                id_string(id),
+                relationship
+            ),
+            Violation::Cardinality {
+                id,
+                relationship,
+                count,
+            } => write!(
+                f,
+                "Entity {} has the wrong number of '{}' edges ({})",
+                id_string(id),
+                relationship,
+                count
+            ),
+            Violation::InvalidTarget {
+                id,
+                relationship,
+                target,
+                component,
+            } => write!(
+                f,
+                "Target {} of '{}' edge from {} is missing component '{}'",
+                id_string(target),
+                relationship,
+                id_string(id),
+                component
+            ),
+        }
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Every way the graph breaks `schema`, in entity iteration order. Empty if it conforms.
+    pub fn validate(&self, schema: &GraphSchema<K, R>) -> Vec<Violation<ID, K, R>> {
+        let mut violations = Vec::new();
+        for (id, components) in &self.entities {
+            for (component, value) in components {
+                if let Some(expected) = schema.shapes.get(component) {
+                    if !expected.matches(value) {
+                        violations.push(Violation::InvalidValue {
+                            id: id.clone(),
+                            component: component.clone(),
+                            expected: *expected,
+                        });
+                    }
+                }
+            }
+
+            let Some(kind) = self.kind_of(&schema.kinds, id) else {
+                continue;
+            };
+            let entity_kind = schema.kinds.get(kind).expect("kind is registered");
+            for component in &entity_kind.components {
+                if !components.contains_key(component) {
+                    violations.push(Violation::MissingComponent {
+                        id: id.clone(),
+                        component: component.clone(),
+                    });
+                }
+            }
+            if let Some(allowed) = &entity_kind.relationships {
+                for relationship in self.relationships.keys() {
+                    if !allowed.contains(relationship) && self.has_edges(relationship, id) {
+                        violations.push(Violation::RelationshipNotAllowed {
+                            id: id.clone(),
+                            relationship: relationship.clone(),
+                        });
+                    }
+                }
+            }
+
+            for (relationship, rule) in schema.rules.get(kind).into_iter().flatten() {
+                let targets = self
+                    .get_neighbors_by_relationship(id, relationship)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if targets.len() < rule.min || rule.max.is_some_and(|max| targets.len() > max) {
+                    violations.push(Violation::Cardinality {
+                        id: id.clone(),
+                        relationship: relationship.clone(),
+                        count: targets.len(),
+                    });
+                }
+                for target in targets {
+                    let target_components = self.entities.get(target);
+                    for component in &rule.target_components {
+                        if !target_components
+                            .is_some_and(|components| components.contains_key(component))
+                        {
+                            violations.push(Violation::InvalidTarget {
+                                id: id.clone(),
+                                relationship: relationship.clone(),
+                                target: target.clone(),
+                                component: component.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        violations
+    }
+
+    fn has_edges(&self, relationship: &R, id: &ID) -> bool {
+        self.get_neighbors_by_relationship(id, relationship)
+            .is_some_and(|targets| !targets.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[test]
+    fn validate_reports_violations() {
+        let mut kinds = KindRegistry::new();
+        kinds
+            .register_with_relationships(
+                "device",
+                ["serial".to_string()],
+                ["has_module".to_string()],
+            )
+            .register("led_module", ["color".to_string()]);
+        let mut schema = GraphSchema::new(kinds);
+        schema
+            .shape("serial".to_string(), ValueShape::String)
+            .relationship(
+                "device",
+                "has_module".to_string(),
+                RelationshipRule::new(1, Some(2)).target_component("id".to_string()),
+            );
+
+        let mut graph = TestGraph::new();
+        let entity = |pairs: &[(&str, Value)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect::<Components<_>>()
+        };
+        graph
+            .add_entity_of_kind(
+                schema.kinds(),
+                "device",
+                "device1".to_string(),
+                entity(&[("serial", Value::from("A-1"))]),
+            )
+            .unwrap();
+        graph
+            .add_entity_of_kind(
+                schema.kinds(),
+                "led_module",
+                "led1".to_string(),
+                entity(&[("color", Value::from("red")), ("id", Value::from(1))]),
+            )
+            .unwrap();
+        graph
+            .add_edge(
+                "has_module".to_string(),
+                "device1".to_string(),
+                "led1".to_string(),
+            )
+            .unwrap();
+        assert!(graph.validate(&schema).is_empty());
+
+        graph
+            .add_entity("device2".to_string(), entity(&[("serial", Value::from(7))]))
+            .unwrap();
+        graph.add_label(&"device2".to_string(), "device").unwrap();
+        graph.add_entity("led2".to_string(), entity(&[])).unwrap();
+        graph.add_label(&"led2".to_string(), "led_module").unwrap();
+        graph
+            .add_edge(
+                "powers".to_string(),
+                "device1".to_string(),
+                "led2".to_string(),
+            )
+            .unwrap();
+        graph
+            .add_edge(
+                "has_module".to_string(),
+                "device1".to_string(),
+                "led2".to_string(),
+            )
+            .unwrap();
+
+        let violations = graph.validate(&schema);
+        let device1 = "device1".to_string();
+        let device2 = "device2".to_string();
+        for expected in [
+            Violation::InvalidValue {
+                id: device2.clone(),
+                component: "serial".to_string(),
+                expected: ValueShape::String,
+            },
+            Violation::Cardinality {
+                id: device2,
+                relationship: "has_module".to_string(),
+                count: 0,
+            },
+            Violation::MissingComponent {
+                id: "led2".to_string(),
+                component: "color".to_string(),
+            },
+            Violation::RelationshipNotAllowed {
+                id: device1.clone(),
+                relationship: "powers".to_string(),
+            },
+            Violation::InvalidTarget {
+                id: device1,
+                relationship: "has_module".to_string(),
+                target: "led2".to_string(),
+                component: "id".to_string(),
+            },
+        ] {
+            assert!(violations.contains(&expected), "{}", expected);
+        }
+        assert_eq!(violations.len(), 5);
+    }
+}
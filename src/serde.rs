@@ -1,38 +1,233 @@
 #![allow(dead_code)]
 
-use crate::description::Error;
+use crate::{description::Error, Description};
 use lazy_static::lazy_static;
-use legion::World;
+use legion::{
+    storage::Component,
+    world::{Duplicate, Entry, EntryRef},
+    World,
+};
 use serde::{de::DeserializeSeed, Deserialize, Serialize};
-use std::sync::RwLock;
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 lazy_static! {
-    pub static ref COMPONENT_REGISTRY: RwLock<legion::Registry<String>> =
-        RwLock::new(legion::Registry::default());
+    static ref GLOBAL_REGISTRY: ComponentRegistry = ComponentRegistry::new();
     pub static ref ENTITY_SERIALIZER: legion::serialize::Canon =
         legion::serialize::Canon::default();
 }
 
+thread_local! {
+    static SCOPED_REGISTRIES: RefCell<Vec<ComponentRegistry>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Default)]
+struct Registrations {
+    registry: legion::Registry<String>,
+    names: HashMap<TypeId, String>,
+    cloners: HashMap<TypeId, Cloner>,
+}
+
+/// How a cloneable component type is copied between worlds.
+#[derive(Clone, Copy)]
+struct Cloner {
+    register: fn(&mut Duplicate),
+    sync: fn(&EntryRef, &mut Entry) -> bool,
+}
+
+/// Copies the `T` of `source` onto `target`, or removes the target's if the source has none.
+/// Values are compared by their serialized form, and whether the target's changed is returned.
+fn sync_component<T: Component + Clone + Serialize>(source: &EntryRef, target: &mut Entry) -> bool {
+    let value = source.get_component::<T>().ok();
+    let unchanged = match (value, target.get_component::<T>().ok()) {
+        (None, None) => true,
+        (Some(value), Some(existing)) => matches!(
+            (serde_json::to_value(value), serde_json::to_value(existing)),
+            (Ok(value), Ok(existing)) if value == existing
+        ),
+        _ => false,
+    };
+    if unchanged {
+        return false;
+    }
+    match value {
+        Some(value) => target.add_component(value.clone()),
+        None => {
+            target.remove_component::<T>();
+        }
+    }
+    true
+}
+
+/// The component types a `Description` can be serialized with. `register_component` and the
+/// other free functions use a global registry; separate registries let Descriptions with
+/// different component sets, or tests running in parallel, keep their registrations apart.
+/// Clones share the same registrations.
+#[derive(Clone, Default)]
+pub struct ComponentRegistry(Arc<RwLock<Registrations>>);
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry used by `register_component` and outside of any `scope`.
+    pub fn global() -> Self {
+        GLOBAL_REGISTRY.clone()
+    }
+
+    /// The registry in effect on this thread: the innermost `scope`, or the global one.
+    pub(crate) fn active() -> Self {
+        SCOPED_REGISTRIES
+            .with(|scoped| scoped.borrow().last().cloned())
+            .unwrap_or_else(Self::global)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Registrations> {
+        self.0
+            .read()
+            .expect("Failed to get the component registry lock!")
+    }
+
+    pub fn register<T: legion::storage::Component + Serialize + for<'de> Deserialize<'de>>(
+        &self,
+        key: &str,
+    ) -> Result<()> {
+        let mut registrations = self.0.write().map_err(|_| Error::AccessComponentRegistry)?;
+        registrations.registry.register::<T>(key.to_string());
+        registrations
+            .names
+            .insert(TypeId::of::<T>(), key.to_string());
+        Ok(())
+    }
+
+    /// Like `register`, also allowing the component to be cloned when nodes are duplicated.
+    /// Registering the same type again replaces its earlier registration.
+    pub fn register_cloneable<
+        T: legion::storage::Component + Clone + Serialize + for<'de> Deserialize<'de>,
+    >(
+        &self,
+        key: &str,
+    ) -> Result<()> {
+        self.register::<T>(key)?;
+        self.0
+            .write()
+            .map_err(|_| Error::AccessComponentRegistry)?
+            .cloners
+            .insert(
+                TypeId::of::<T>(),
+                Cloner {
+                    register: |merger| merger.register_clone::<T>(),
+                    sync: sync_component::<T>,
+                },
+            );
+        Ok(())
+    }
+
+    /// Whether any component type has been registered under `key`.
+    pub fn is_registered(&self, key: &str) -> bool {
+        self.read().names.values().any(|name| name == key)
+    }
+
+    /// Runs `f` with this registry in place of the global one on the current thread, so
+    /// Descriptions serialized, deserialized or duplicated inside it use these registrations.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Pop;
+        impl Drop for Pop {
+            fn drop(&mut self) {
+                SCOPED_REGISTRIES.with(|scoped| scoped.borrow_mut().pop());
+            }
+        }
+        SCOPED_REGISTRIES.with(|scoped| scoped.borrow_mut().push(self.clone()));
+        let _pop = Pop;
+        f()
+    }
+
+    /// The description as JSON, with its components serialized using this registry.
+    pub fn serialize_description(&self, description: &Description) -> serde_json::Result<String> {
+        self.scope(|| serde_json::to_string(description))
+    }
+
+    /// Reads a description written by `serialize_description` using this registry.
+    pub fn deserialize_description(&self, data: &str) -> serde_json::Result<Description> {
+        self.scope(|| serde_json::from_str(data))
+    }
+}
+
 pub fn register_component<T: legion::storage::Component + Serialize + for<'de> Deserialize<'de>>(
     key: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut registry = COMPONENT_REGISTRY
-        .write()
-        .map_err(|_| Error::AccessComponentRegistry)?;
-    registry.register::<T>(key.to_string());
-    Ok(())
+    GLOBAL_REGISTRY.register::<T>(key).map_err(Into::into)
+}
+
+/// Registers a component for serialization like `register_component`, and also allows it to
+/// be cloned when nodes are duplicated.
+pub fn register_cloneable_component<
+    T: legion::storage::Component + Clone + Serialize + for<'de> Deserialize<'de>,
+>(
+    key: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    GLOBAL_REGISTRY
+        .register_cloneable::<T>(key)
+        .map_err(Into::into)
+}
+
+/// A merger that clones every component type registered with `register_cloneable_component`.
+pub fn component_cloner() -> Duplicate {
+    let mut merger = Duplicate::default();
+    for cloner in ComponentRegistry::active().read().cloners.values() {
+        (cloner.register)(&mut merger);
+    }
+    merger
+}
+
+/// Brings every component type registered with `register_cloneable_component` on `target` in
+/// line with `source`, returning the types whose values changed. Other components are kept.
+pub fn sync_cloneable_components(source: &EntryRef, target: &mut Entry) -> Vec<TypeId> {
+    ComponentRegistry::active()
+        .read()
+        .cloners
+        .iter()
+        .filter(|(_, cloner)| (cloner.sync)(source, target))
+        .map(|(type_id, _)| *type_id)
+        .collect()
+}
+
+/// Whether the component type has been registered with `register_cloneable_component`.
+pub fn is_component_cloneable(type_id: TypeId) -> bool {
+    ComponentRegistry::active()
+        .read()
+        .cloners
+        .contains_key(&type_id)
+}
+
+/// Whether any component type has been registered under `key`.
+pub fn is_component_registered(key: &str) -> bool {
+    ComponentRegistry::active().is_registered(key)
+}
+
+/// The key a component type was registered under, if any.
+pub fn registered_component_name(type_id: TypeId) -> Option<String> {
+    ComponentRegistry::active()
+        .read()
+        .names
+        .get(&type_id)
+        .cloned()
 }
 
 pub fn serialize_ecs<S>(ecs: &World, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
-    let registry = COMPONENT_REGISTRY
-        .read()
-        .expect("Failed to get the component registry lock!");
-    ecs.as_serializable(legion::any(), &*registry, &*ENTITY_SERIALIZER)
+    let registry = ComponentRegistry::active();
+    let registrations = registry.read();
+    ecs.as_serializable(legion::any(), &registrations.registry, &*ENTITY_SERIALIZER)
         .serialize(serializer)
 }
 
@@ -40,9 +235,44 @@ pub fn deserialize_ecs<'de, D>(deserializer: D) -> Result<World, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    COMPONENT_REGISTRY
+    ComponentRegistry::active()
         .read()
-        .expect("Failed to get the component registry lock!")
+        .registry
         .as_deserialize(&*ENTITY_SERIALIZER)
         .deserialize(deserializer)
 }
+
+/// Serializes a value containing `Entity` handles using the same entity serializer as the world,
+/// so the handles resolve to the same entities when deserialized.
+pub fn serialize_with_entities<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: serde::Serializer,
+{
+    legion::serialize::set_entity_serializer(&*ENTITY_SERIALIZER, || value.serialize(serializer))
+}
+
+pub fn deserialize_with_entities<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    legion::serialize::set_entity_serializer(&*ENTITY_SERIALIZER, || T::deserialize(deserializer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Transform(f32);
+
+    #[test]
+    fn cloneable_registration_is_idempotent() -> Result<()> {
+        let registry = ComponentRegistry::new();
+        registry.register_cloneable::<Transform>("transform")?;
+        registry.register_cloneable::<Transform>("transform")?;
+        assert_eq!(registry.read().cloners.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! `proptest` strategies for `EntityGraph`. Edges are generated as indices into the graph's
+//! entities, so shrinking away entities, edges, labels or components always leaves a valid
+//! graph with every edge pointing at an existing entity.
+
+use crate::EntityGraph;
+use proptest::{
+    collection::{hash_map, hash_set, vec},
+    option,
+    prelude::*,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// Size limits for the graphs produced by `EntityGraph`'s `Arbitrary` impl.
+#[derive(Debug, Copy, Clone)]
+pub struct GraphParams {
+    pub max_entities: usize,
+    pub max_components: usize,
+}
+
+impl Default for GraphParams {
+    fn default() -> Self {
+        Self {
+            max_entities: 16,
+            max_components: 3,
+        }
+    }
+}
+
+/// A component value: null, a bool, an integer or a short string.
+pub fn component_value() -> impl Strategy<Value = Value> + Clone {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        "[a-z]{0,8}".prop_map(Value::from),
+    ]
+}
+
+/// Graphs with up to `params.max_entities` entities drawn from `id`, components keyed by
+/// `key`, an optional label per entity, and up to two edges per entity in relationships
+/// drawn from `relationship`.
+pub fn entity_graph<ID, K, R>(
+    id: impl Strategy<Value = ID> + 'static,
+    key: impl Strategy<Value = K> + Clone + 'static,
+    relationship: impl Strategy<Value = R> + Clone + 'static,
+    params: GraphParams,
+) -> BoxedStrategy<EntityGraph<ID, K, R>>
+where
+    ID: Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + 'static,
+    K: Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + Display + 'static,
+    R: Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + Display + 'static,
+{
+    hash_set(id, 0..=params.max_entities)
+        .prop_flat_map(move |ids| {
+            let ids = ids.into_iter().collect::<Vec<_>>();
+            let count = ids.len();
+            let components = vec(
+                hash_map(key.clone(), component_value(), 0..=params.max_components),
+                count,
+            );
+            let labels = vec(option::of("[a-c]"), count);
+            let edges = if count == 0 {
+                Just(Vec::new()).boxed()
+            } else {
+                vec((relationship.clone(), 0..count, 0..count), 0..=count * 2).boxed()
+            };
+            (Just(ids), components, labels, edges)
+        })
+        .prop_map(|(ids, components, labels, edges)| {
+            let mut graph = EntityGraph::new();
+            for (id, components) in ids.iter().zip(components) {
+                graph
+                    .add_entity(id.clone(), components.into_iter().collect())
+                    .unwrap();
+            }
+            for (id, label) in ids.iter().zip(labels) {
+                if let Some(label) = label {
+                    graph.add_label(id, &label).unwrap();
+                }
+            }
+            for (relationship, from, to) in edges {
+                graph
+                    .add_edge(relationship, ids[from].clone(), ids[to].clone())
+                    .unwrap();
+            }
+            graph
+        })
+        .boxed()
+}
+
+impl<ID, K, R> Arbitrary for EntityGraph<ID, K, R>
+where
+    ID: Arbitrary + Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + 'static,
+    K: Arbitrary + Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + Display + 'static,
+    R: Arbitrary + Eq + Hash + Clone + Debug + Serialize + DeserializeOwned + Display + 'static,
+    K::Strategy: Clone,
+    R::Strategy: Clone,
+{
+    type Parameters = GraphParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(params: GraphParams) -> Self::Strategy {
+        entity_graph(any::<ID>(), any::<K>(), any::<R>(), params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::GeneratedGraph;
+
+    fn small_graph() -> BoxedStrategy<GeneratedGraph> {
+        entity_graph(
+            0..64usize,
+            "[a-d]",
+            "edge[0-2]",
+            GraphParams {
+                max_entities: 12,
+                ..GraphParams::default()
+            },
+        )
+    }
+
+    fn sorted_edges(mut graph: GeneratedGraph) -> GeneratedGraph {
+        for adjacency_list in graph.relationships.values_mut() {
+            for neighbors in adjacency_list.edges.values_mut() {
+                neighbors.sort_unstable();
+            }
+        }
+        graph
+    }
+
+    fn incoming_is_consistent(graph: &GeneratedGraph) -> bool {
+        let sorted = |relationships: &crate::graph::Relationships<usize, String>| {
+            let mut edges = relationships
+                .iter()
+                .flat_map(|(relationship, adjacency_list)| {
+                    adjacency_list.edges.iter().flat_map(move |(to, sources)| {
+                        sources
+                            .iter()
+                            .map(move |from| (relationship.clone(), *to, *from))
+                    })
+                })
+                .collect::<Vec<_>>();
+            edges.sort();
+            edges
+        };
+        sorted(&graph.incoming)
+            == sorted(&crate::graph::reverse_relationships(&graph.relationships))
+    }
+
+    proptest! {
+        #[test]
+        fn edges_point_at_entities(graph in any::<EntityGraph<u8, String, String>>()) {
+            for relationship in graph.relationships() {
+                for (from, to) in graph.edges(relationship) {
+                    prop_assert!(graph.entities.contains_key(from));
+                    prop_assert!(graph.entities.contains_key(to));
+                }
+            }
+        }
+
+        #[test]
+        fn json_round_trip(graph in small_graph()) {
+            let json = graph.serialize().unwrap();
+            prop_assert_eq!(serde_json::from_str::<GeneratedGraph>(&json).unwrap(), graph);
+        }
+
+        #[test]
+        fn freeze_thaw_round_trip(graph in small_graph()) {
+            let thawed = graph.freeze().thaw();
+            prop_assert!(incoming_is_consistent(&thawed));
+            prop_assert_eq!(thawed, graph);
+        }
+
+        #[test]
+        fn partition_round_trip(graph in small_graph()) {
+            // Reassembly restores in-shard edges ahead of cut edges, so only compare edge sets.
+            let reassembled = GeneratedGraph::reassemble(graph.partition(3)).unwrap();
+            prop_assert_eq!(sorted_edges(reassembled), sorted_edges(graph));
+        }
+
+        #[test]
+        fn change_export_round_trip(graph in small_graph()) {
+            let mut replica = GeneratedGraph::new();
+            replica.import_changes(graph.export_changes_since(0)).unwrap();
+            prop_assert!(incoming_is_consistent(&replica));
+            prop_assert_eq!(replica, graph);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Assertions for comparing graphs in tests, with failure messages that list what differs
+//! instead of dumping both graphs.
+
+use crate::{graph::id_string, Description, EntityGraph};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+/// The differences between two graphs, one per line: `-` for what only the left graph has,
+/// `+` for what only the right graph has and `~` for values that changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GraphDiff {
+    lines: Vec<String>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Adds a line for every record of `left` missing from `right` and vice versa, counting
+    /// repeated records.
+    fn compare(&mut self, left: Vec<String>, right: Vec<String>) {
+        let count = |records: Vec<String>| {
+            let mut counts = BTreeMap::<String, usize>::new();
+            for record in records {
+                *counts.entry(record).or_default() += 1;
+            }
+            counts
+        };
+        let (left, right) = (count(left), count(right));
+        for (record, &left_count) in &left {
+            let right_count = right.get(record).copied().unwrap_or_default();
+            for _ in right_count..left_count {
+                self.lines.push(format!("- {record}"));
+            }
+        }
+        for (record, &right_count) in &right {
+            let left_count = left.get(record).copied().unwrap_or_default();
+            for _ in left_count..right_count {
+                self.lines.push(format!("+ {record}"));
+            }
+        }
+    }
+
+    /// Compares two maps of values, reporting changed values with `~`.
+    fn compare_values(&mut self, left: BTreeMap<String, Value>, right: BTreeMap<String, Value>) {
+        for (key, value) in &left {
+            match right.get(key) {
+                None => self.lines.push(format!("- {key} = {value}")),
+                Some(other) if other != value => {
+                    self.lines.push(format!("~ {key}: {value} -> {other}"))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in &right {
+            if !left.contains_key(key) {
+                self.lines.push(format!("+ {key} = {value}"));
+            }
+        }
+    }
+}
+
+impl Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Graphs that `assert_graph_eq!` and `assert_has_edge!` can check.
+pub trait GraphAssertions {
+    type Relationship: ?Sized;
+    type Node: ?Sized;
+
+    fn diff(&self, other: &Self) -> GraphDiff;
+
+    /// `Err` with a readable message if there is no `from -> to` edge of `relationship`.
+    fn check_edge(
+        &self,
+        relationship: &Self::Relationship,
+        from: &Self::Node,
+        to: &Self::Node,
+    ) -> Result<(), String>;
+}
+
+impl<ID, K, R> GraphAssertions for EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    type Relationship = R;
+    type Node = ID;
+
+    fn diff(&self, other: &Self) -> GraphDiff {
+        let entities = |graph: &Self| {
+            graph
+                .entities
+                .keys()
+                .map(|id| format!("entity {}", id_string(id)))
+                .collect()
+        };
+        let components = |graph: &Self| {
+            graph
+                .entities
+                .iter()
+                .filter(|(id, _)| {
+                    self.entities.contains_key(*id) && other.entities.contains_key(*id)
+                })
+                .flat_map(|(id, components)| {
+                    components.iter().map(move |(key, value)| {
+                        (format!("{}.{}", id_string(id), key), value.clone())
+                    })
+                })
+                .collect()
+        };
+        let labels = |graph: &Self| {
+            graph
+                .labels
+                .iter()
+                .flat_map(|(id, labels)| {
+                    labels
+                        .iter()
+                        .map(move |label| format!("label {} '{}'", id_string(id), label))
+                })
+                .collect()
+        };
+        let edges = |graph: &Self| {
+            graph
+                .relationships
+                .iter()
+                .flat_map(|(relationship, adjacency_list)| {
+                    adjacency_list
+                        .edges
+                        .iter()
+                        .flat_map(move |(from, neighbors)| {
+                            neighbors.iter().map(move |to| {
+                                let record = format!(
+                                    "{}: {} -> {}",
+                                    relationship,
+                                    id_string(from),
+                                    id_string(to)
+                                );
+                                match adjacency_list.edge_data(from, to) {
+                                    Some(data) => format!("{record} = {data}"),
+                                    None => record,
+                                }
+                            })
+                        })
+                })
+                .collect()
+        };
+
+        let mut diff = GraphDiff::default();
+        diff.compare(entities(self), entities(other));
+        diff.compare_values(components(self), components(other));
+        diff.compare(labels(self), labels(other));
+        diff.compare(edges(self), edges(other));
+        diff
+    }
+
+    fn check_edge(&self, relationship: &R, from: &ID, to: &ID) -> Result<(), String> {
+        let neighbors = self.get_neighbors_by_relationship(from, relationship);
+        if neighbors.is_some_and(|neighbors| neighbors.contains(to)) {
+            return Ok(());
+        }
+        let existing = neighbors
+            .into_iter()
+            .flatten()
+            .map(id_string)
+            .collect::<Vec<_>>();
+        Err(format!(
+            "no '{}' edge from {} to {}; its '{}' neighbors are [{}]",
+            relationship,
+            id_string(from),
+            id_string(to),
+            relationship,
+            existing.join(", ")
+        ))
+    }
+}
+
+impl Description {
+    fn edge_records(&self) -> Vec<String> {
+        self.graphs
+            .iter()
+            .flat_map(|(edge_name, graph)| {
+                graph.edge_references().map(move |edge| {
+                    let from = self.name_of(graph[edge.source()]).unwrap_or_default();
+                    let to = self.name_of(graph[edge.target()]).unwrap_or_default();
+                    format!("{edge_name}: {from} -> {to}")
+                })
+            })
+            .collect()
+    }
+}
+
+impl GraphAssertions for Description {
+    type Relationship = str;
+    type Node = str;
+
+    /// Compares node names, registered component values and edges. Components whose types
+    /// aren't registered are not compared.
+    fn diff(&self, other: &Self) -> GraphDiff {
+        let nodes = |description: &Self| {
+            description
+                .node_name_to_entity
+                .keys()
+                .map(|name| format!("node '{name}'"))
+                .collect()
+        };
+        let components = |description: &Self| {
+            description
+                .registered_components()
+                .into_iter()
+                .filter(|(name, _)| {
+                    self.entity_of(name).is_some() && other.entity_of(name).is_some()
+                })
+                .flat_map(|(name, components)| {
+                    let Value::Object(components) = components else {
+                        return Vec::new();
+                    };
+                    components
+                        .into_iter()
+                        .map(|(key, value)| (format!("{name}.{key}"), value))
+                        .collect()
+                })
+                .collect()
+        };
+
+        let mut diff = GraphDiff::default();
+        diff.compare(nodes(self), nodes(other));
+        diff.compare_values(components(self), components(other));
+        diff.compare(self.edge_records(), other.edge_records());
+        diff
+    }
+
+    fn check_edge(&self, edge_name: &str, from: &str, to: &str) -> Result<(), String> {
+        if self
+            .edge_count_between(edge_name, from, to)
+            .unwrap_or_default()
+            > 0
+        {
+            return Ok(());
+        }
+        let prefix = format!("{edge_name}: {from} -> ");
+        let existing = self
+            .edge_records()
+            .into_iter()
+            .filter_map(|record| record.strip_prefix(&prefix).map(str::to_string))
+            .collect::<BTreeSet<_>>();
+        Err(format!(
+            "no '{edge_name}' edge from '{from}' to '{to}'; its '{edge_name}' targets are [{}]",
+            existing.into_iter().collect::<Vec<_>>().join(", ")
+        ))
+    }
+}
+
+/// Asserts that two `EntityGraph`s or `Description`s are equal, listing their differences on
+/// failure.
+#[macro_export]
+macro_rules! assert_graph_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let diff = $crate::testing::GraphAssertions::diff(left, right);
+                if !diff.is_empty() {
+                    panic!("assertion `left == right` failed: graphs differ\n{}", diff);
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($message:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let diff = $crate::testing::GraphAssertions::diff(left, right);
+                if !diff.is_empty() {
+                    panic!(
+                        "assertion `left == right` failed: {}\n{}",
+                        format_args!($($message)+),
+                        diff
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Asserts that a graph has a `from -> to` edge of the given relationship, listing the
+/// existing edges from `from` on failure.
+#[macro_export]
+macro_rules! assert_has_edge {
+    ($graph:expr, $relationship:expr, $from:expr, $to:expr $(,)?) => {
+        if let Err(message) =
+            $crate::testing::GraphAssertions::check_edge(&$graph, $relationship, $from, $to)
+        {
+            panic!("assertion failed: {}", message);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Components;
+    use crate::{register_component, DescriptionBuilder, EntityGraph};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    use super::GraphAssertions;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn graph(mass: i64) -> TestGraph {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "A".to_string(),
+                Components::from([("mass".to_string(), Value::from(mass))]),
+            )
+            .unwrap();
+        graph
+            .add_entity("B".to_string(), Components::new())
+            .unwrap();
+        graph
+            .add_edge("link".to_string(), "A".to_string(), "B".to_string())
+            .unwrap();
+        graph
+    }
+
+    #[test]
+    fn entity_graph_diff() {
+        assert_graph_eq!(graph(1), graph(1));
+        assert_has_edge!(
+            graph(1),
+            &"link".to_string(),
+            &"A".to_string(),
+            &"B".to_string()
+        );
+
+        let mut other = graph(2);
+        other
+            .add_entity("C".to_string(), Components::new())
+            .unwrap();
+        other
+            .add_edge("link".to_string(), "A".to_string(), "C".to_string())
+            .unwrap();
+        assert_eq!(
+            graph(1).diff(&other).lines(),
+            [
+                "+ entity \"C\"",
+                "~ \"A\".mass: 1 -> 2",
+                "+ link: \"A\" -> \"C\"",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no 'link' edge from \"B\" to \"A\"")]
+    fn assert_has_edge_reports_missing_edge() {
+        assert_has_edge!(
+            graph(1),
+            &"link".to_string(),
+            &"B".to_string(),
+            &"A".to_string()
+        );
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Speed(u32);
+
+    #[test]
+    fn description_diff() {
+        register_component::<Speed>("testing_speed").unwrap();
+        let build = |speed, target| {
+            let mut builder = DescriptionBuilder::new();
+            builder
+                .add_node("motor".to_string(), (Speed(speed),))
+                .unwrap();
+            builder.add_node("wheel".to_string(), (Speed(0),)).unwrap();
+            builder.add_node("axle".to_string(), (Speed(0),)).unwrap();
+            builder.add_edge("drives", "motor", vec![target]).unwrap();
+            builder.build()
+        };
+        assert_graph_eq!(build(3, "wheel"), build(3, "wheel"));
+        assert_has_edge!(build(3, "wheel"), "drives", "motor", "wheel");
+        assert_eq!(
+            build(3, "wheel").diff(&build(4, "axle")).lines(),
+            [
+                "~ motor.testing_speed: 3 -> 4",
+                "- drives: motor -> wheel",
+                "+ drives: motor -> axle",
+            ]
+        );
+    }
+}
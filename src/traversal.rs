@@ -0,0 +1,350 @@
+use crate::{Description, EntityGraph, Error};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Borrow,
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    hash::Hash,
+};
+
+/// Which edges a traversal follows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From source to target.
+    #[default]
+    Outgoing,
+    /// From target back to source.
+    Incoming,
+    /// Either way.
+    Both,
+}
+
+impl Direction {
+    pub(crate) fn includes_outgoing(self) -> bool {
+        matches!(self, Direction::Outgoing | Direction::Both)
+    }
+
+    pub(crate) fn includes_incoming(self) -> bool {
+        matches!(self, Direction::Incoming | Direction::Both)
+    }
+}
+
+/// Decides whether a traversal may visit a node.
+pub type NodeFilter<'a, N> = Box<dyn Fn(&N) -> bool + 'a>;
+
+/// Limits what a traversal visits. The default walks every relationship along outgoing edges
+/// with no depth limit.
+///
+/// `N` is the type nodes are identified by: the entity ID for an `EntityGraph`, `str` for a
+/// `Description`. `R` is the relationship, or edge name, type.
+pub struct TraversalOptions<'a, N: ?Sized, R> {
+    pub max_depth: Option<usize>,
+    pub relationships: Option<Vec<R>>,
+    pub direction: Direction,
+    /// Nodes for which this returns `false` are neither visited nor walked through. The start
+    /// node is always visited.
+    pub filter: Option<NodeFilter<'a, N>>,
+}
+
+impl<N: ?Sized, R> Default for TraversalOptions<'_, N, R> {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            relationships: None,
+            direction: Direction::default(),
+            filter: None,
+        }
+    }
+}
+
+impl<'a, N: ?Sized, R> TraversalOptions<'a, N, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn relationships(mut self, relationships: impl IntoIterator<Item = R>) -> Self {
+        self.relationships = Some(relationships.into_iter().collect());
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn filter(mut self, filter: impl Fn(&N) -> bool + 'a) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    fn allows(&self, node: &N) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter(node))
+    }
+
+    fn descends_from(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth < max_depth)
+    }
+
+    /// Visits nodes from `start` depth-first, returning each with its depth.
+    fn depth_first<T, F>(&self, start: T, mut neighbors: F) -> Vec<(T, usize)>
+    where
+        T: Clone + Eq + Hash + Borrow<N>,
+        F: FnMut(&T) -> Vec<T>,
+    {
+        let mut visited = HashSet::new();
+        let mut stack = vec![(start, 0)];
+        let mut result = Vec::new();
+        while let Some((current, depth)) = stack.pop() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            if self.descends_from(depth) {
+                for neighbor in neighbors(&current) {
+                    if !visited.contains::<T>(&neighbor) && self.allows(neighbor.borrow()) {
+                        stack.push((neighbor, depth + 1));
+                    }
+                }
+            }
+            result.push((current, depth));
+        }
+        result
+    }
+
+    /// Visits nodes from `start` breadth-first, returning each with its shortest depth.
+    fn breadth_first<T, F>(&self, start: T, mut neighbors: F) -> Vec<(T, usize)>
+    where
+        T: Clone + Eq + Hash + Borrow<N>,
+        F: FnMut(&T) -> Vec<T>,
+    {
+        let mut visited = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut result = Vec::new();
+        while let Some((current, depth)) = queue.pop_front() {
+            if self.descends_from(depth) {
+                for neighbor in neighbors(&current) {
+                    if self.allows(neighbor.borrow()) && visited.insert(neighbor.clone()) {
+                        queue.push_back((neighbor, depth + 1));
+                    }
+                }
+            }
+            result.push((current, depth));
+        }
+        result
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Depth-first traversal from `start` limited by `options`. Each visited ID is returned
+    /// with its depth, `start` being at depth 0.
+    pub fn traverse_dfs_with(
+        &self,
+        start: ID,
+        options: &TraversalOptions<'_, ID, R>,
+    ) -> Vec<(ID, usize)> {
+        options.depth_first(start, |id| self.directed_neighbors(id, options))
+    }
+
+    /// Breadth-first counterpart of `traverse_dfs_with`. Depths are shortest distances over
+    /// the followed edges.
+    pub fn traverse_bfs_with(
+        &self,
+        start: ID,
+        options: &TraversalOptions<'_, ID, R>,
+    ) -> Vec<(ID, usize)> {
+        options.breadth_first(start, |id| self.directed_neighbors(id, options))
+    }
+
+    fn directed_neighbors(&self, id: &ID, options: &TraversalOptions<'_, ID, R>) -> Vec<ID> {
+        let relationships = options.relationships.as_deref();
+        let mut neighbors = Vec::new();
+        if options.direction.includes_outgoing() {
+            neighbors.extend(
+                self.filtered_neighbors(id, relationships)
+                    .into_iter()
+                    .cloned(),
+            );
+        }
+        if options.direction.includes_incoming() {
+            let incoming: Vec<&ID> = match relationships {
+                Some(relationships) => relationships
+                    .iter()
+                    .filter_map(|relationship| {
+                        self.get_incoming_neighbors_by_relationship(id, relationship)
+                    })
+                    .flatten()
+                    .collect(),
+                None => self.get_incoming_neighbors(id).unwrap_or_default(),
+            };
+            neighbors.extend(incoming.into_iter().cloned());
+        }
+        neighbors
+    }
+}
+
+impl Description {
+    /// Depth-first traversal from the node called `start` limited by `options`, whose
+    /// relationships are edge names. Each visited node name is returned with its depth, `start`
+    /// being at depth 0.
+    pub fn traverse_dfs(
+        &self,
+        start: &str,
+        options: &TraversalOptions<'_, str, String>,
+    ) -> Result<Vec<(String, usize)>, Error> {
+        let start = self.checked_node_name(start)?;
+        Ok(options.depth_first(start, |name| self.directed_neighbors(name, options)))
+    }
+
+    /// Breadth-first counterpart of `traverse_dfs`. Depths are shortest distances over the
+    /// followed edges.
+    pub fn traverse_bfs(
+        &self,
+        start: &str,
+        options: &TraversalOptions<'_, str, String>,
+    ) -> Result<Vec<(String, usize)>, Error> {
+        let start = self.checked_node_name(start)?;
+        Ok(options.breadth_first(start, |name| self.directed_neighbors(name, options)))
+    }
+
+    fn checked_node_name(&self, name: &str) -> Result<String, Error> {
+        self.entity_of(name)
+            .map(|_| name.to_string())
+            .ok_or_else(|| Error::NodeNotFound {
+                name: name.to_string(),
+            })
+    }
+
+    /// Names of the nodes connected to `name` by edges matching `options`, in any graph.
+    pub(crate) fn directed_neighbors(
+        &self,
+        name: &str,
+        options: &TraversalOptions<'_, str, String>,
+    ) -> Vec<String> {
+        let Some(entity) = self.entity_of(name) else {
+            return Vec::new();
+        };
+        let mut neighbors = Vec::new();
+        for (edge_name, graph) in &self.graphs {
+            if options
+                .relationships
+                .as_ref()
+                .is_some_and(|relationships| !relationships.contains(edge_name))
+            {
+                continue;
+            }
+            let Some(index) = self.node_index(edge_name, entity) else {
+                continue;
+            };
+            if options.direction.includes_outgoing() {
+                for edge in graph.edges_directed(index, petgraph::Direction::Outgoing) {
+                    neighbors.extend(self.name_of(graph[edge.target()]).map(str::to_string));
+                }
+            }
+            if options.direction.includes_incoming() {
+                for edge in graph.edges_directed(index, petgraph::Direction::Incoming) {
+                    neighbors.extend(self.name_of(graph[edge.source()]).map(str::to_string));
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use crate::DescriptionBuilder;
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    fn chain() -> TestGraph {
+        let mut graph = TestGraph::new();
+        for id in ["A", "B", "C", "D", "E"] {
+            graph.add_entity(id.to_string(), Components::new()).unwrap();
+        }
+        for (relationship, from, to) in [
+            ("next", "A", "B"),
+            ("next", "B", "C"),
+            ("next", "C", "D"),
+            ("skip", "A", "E"),
+        ] {
+            graph
+                .add_edge(relationship.to_string(), from.to_string(), to.to_string())
+                .unwrap();
+        }
+        graph
+    }
+
+    fn ids(visited: Vec<(String, usize)>) -> Vec<String> {
+        let mut ids = visited.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn entity_graph_traversal_options() {
+        let graph = chain();
+        let options = TraversalOptions::new()
+            .relationships(["next".to_string()])
+            .filter(|id: &String| id != "C");
+        assert_eq!(
+            ids(graph.traverse_dfs_with("A".to_string(), &options)),
+            ["A", "B"]
+        );
+
+        let options = TraversalOptions::new()
+            .direction(Direction::Incoming)
+            .max_depth(2);
+        assert_eq!(
+            graph.traverse_bfs_with("D".to_string(), &options),
+            vec![
+                ("D".to_string(), 0),
+                ("C".to_string(), 1),
+                ("B".to_string(), 2)
+            ]
+        );
+
+        let options = TraversalOptions::new().direction(Direction::Both);
+        assert_eq!(
+            ids(graph.traverse_bfs_with("E".to_string(), &options)).len(),
+            5
+        );
+    }
+
+    #[test]
+    fn description_traversal_options() -> Result<(), Error> {
+        let mut builder = DescriptionBuilder::new();
+        builder.allow_implicit_nodes(true);
+        builder.add_edge("contains", "plant", vec!["line1", "line2"])?;
+        builder.add_edge("contains", "line1", vec!["press"])?;
+        builder.add_edge("feeds", "press", vec!["line2"])?;
+        let description = builder.build();
+
+        let options = TraversalOptions::new().relationships(["contains".to_string()]);
+        assert_eq!(
+            ids(description.traverse_dfs("plant", &options)?),
+            ["line1", "line2", "plant", "press"]
+        );
+
+        let options = TraversalOptions::new()
+            .direction(Direction::Incoming)
+            .filter(|name: &str| name != "line1");
+        assert_eq!(
+            ids(description.traverse_bfs("line2", &options)?),
+            ["line2", "plant", "press"]
+        );
+        assert!(description.traverse_dfs("missing", &options).is_err());
+        Ok(())
+    }
+}
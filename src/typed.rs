@@ -0,0 +1,127 @@
+use crate::{AnyMap, EntityGraph, EntityGraphError, TypeRegistry};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+/// Each entity's components as the types registered for their keys, one `AnyMap` per entity.
+/// Components are stored by type, so of several components of the same type on one entity
+/// only the one whose key sorts last is kept; `get_component` still has them all.
+pub struct TypedComponents<ID: Eq + Hash> {
+    entities: HashMap<ID, AnyMap>,
+}
+
+impl<ID: Eq + Hash> TypedComponents<ID> {
+    /// The entity's component of type `T`.
+    pub fn get_component_as<T: 'static>(&self, id: &ID) -> Option<&T> {
+        self.entities.get(id)?.find::<T>()
+    }
+
+    pub fn get_component_as_mut<T: 'static>(&mut self, id: &ID) -> Option<&mut T> {
+        self.entities.get_mut(id)?.find_mut::<T>()
+    }
+
+    /// All of the entity's typed components.
+    pub fn components(&self, id: &ID) -> Option<&AnyMap> {
+        self.entities.get(id)
+    }
+}
+
+impl<ID, K, R> EntityGraph<ID, K, R>
+where
+    ID: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de>,
+    K: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+    R: Eq + Hash + Clone + Serialize + for<'de> Deserialize<'de> + Display,
+{
+    /// Every entity's components deserialized as the types registered for their keys. Fails
+    /// like `deserialize_with_registry` if a key has no registered type or a value doesn't
+    /// fit it.
+    pub fn typed_components(
+        &self,
+        registry: &TypeRegistry,
+    ) -> Result<TypedComponents<ID>, EntityGraphError> {
+        let mut entities = HashMap::with_capacity(self.entities.len());
+        for (id, components) in &self.entities {
+            let mut components = components
+                .iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect::<Vec<_>>();
+            components.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut typed = AnyMap::new();
+            for (key, value) in components {
+                let value = registry.deserialize_typed(&key, value).map_err(|e| {
+                    EntityGraphError::DeserializationError(format!(
+                        "Failed to deserialize component {}: {}",
+                        key, e
+                    ))
+                })?;
+                typed.insert_boxed(value);
+            }
+            entities.insert(id.clone(), typed);
+        }
+        Ok(TypedComponents { entities })
+    }
+
+    /// Like `deserialize_with_registry`, also returning every entity's components as their
+    /// registered types.
+    pub fn deserialize_with_typed_components(
+        data: &str,
+        registry: &TypeRegistry,
+    ) -> Result<(Self, TypedComponents<ID>), EntityGraphError> {
+        let graph = Self::deserialize_with_registry(data, registry)?;
+        let typed = graph.typed_components(registry)?;
+        Ok((graph, typed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Components;
+    use serde_json::{json, Value};
+
+    type TestGraph = EntityGraph<String, String, String>;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[test]
+    fn typed_components_after_load() {
+        let mut graph = TestGraph::new();
+        graph
+            .add_entity(
+                "led".to_string(),
+                Components::from([
+                    ("position".to_string(), json!({ "x": 1.0, "y": 2.0 })),
+                    ("name".to_string(), Value::from("status")),
+                ]),
+            )
+            .unwrap();
+        let mut registry = TypeRegistry::new();
+        registry.register::<Position>("position");
+        registry.register::<String>("name");
+
+        let serialized = graph.serialize().unwrap();
+        let (loaded, mut typed) =
+            TestGraph::deserialize_with_typed_components(&serialized, &registry).unwrap();
+        assert_eq!(loaded, graph);
+        let led = "led".to_string();
+        assert_eq!(
+            typed.get_component_as::<Position>(&led),
+            Some(&Position { x: 1.0, y: 2.0 })
+        );
+        assert_eq!(
+            typed.get_component_as::<String>(&led).map(String::as_str),
+            Some("status")
+        );
+        assert!(typed.get_component_as::<u32>(&led).is_none());
+        typed.get_component_as_mut::<Position>(&led).unwrap().x = 5.0;
+        assert_eq!(typed.get_component_as::<Position>(&led).unwrap().x, 5.0);
+
+        let mut registry = TypeRegistry::new();
+        registry.register::<Position>("position");
+        registry.register::<u32>("name");
+        assert!(graph.typed_components(&registry).is_err());
+    }
+}